@@ -80,9 +80,15 @@ fn parse_arg(arg: String) -> ArgIter {
 fn print_usage(subcommand: Option<Subcommand>) {
     match subcommand {
         None => println!("usage: site [-h] {{build,deploy,serve}} ..."),
-        Some(Subcommand::Build) => println!("usage: site build [-h] [-w] [-f] [--ignore-errors]"),
+        Some(Subcommand::Build) => {
+            println!("usage: site build [-h] [-w] [-f] [--ignore-errors] [-z]")
+        }
         Some(Subcommand::Deploy) => println!("usage: site deploy [-h]"),
-        Some(Subcommand::Serve) => println!("usage: site serve [-h] [-w]"),
+        Some(Subcommand::Serve) => {
+            println!(
+                "usage: site serve [-h] [-w] [--poll] [--host HOST] [--port PORT] [-q]\n                  [--autoindex] [-m]"
+            )
+        }
     }
 }
 
@@ -106,6 +112,7 @@ fn print_help(subcommand: Option<Subcommand>) {
             println!("  -w, --write      write output instead of simply performing a dry run");
             println!("  -f, --force      delete output folder before writing");
             println!("  --ignore-errors  ignore errors during input processing");
+            println!("  -z, --gzip       also write gzip-compressed copies of text outputs");
         }
         Some(Subcommand::Deploy) => {
             println!("options:");
@@ -113,8 +120,23 @@ fn print_help(subcommand: Option<Subcommand>) {
         }
         Some(Subcommand::Serve) => {
             println!("options:");
-            println!("  -h, --help   show this help message and exit");
-            println!("  -w, --watch  watch for changes and automatically regenerate files");
+            println!("  -h, --help    show this help message and exit");
+            println!("  -w, --watch   watch for changes and automatically regenerate files");
+            println!("  --poll        poll for changes instead of using native notifications");
+            println!(
+                "  --host HOST   address to listen on, IPv6 included (default: {})",
+                conf::HOST
+            );
+            println!(
+                "  --port PORT   port to listen on, 0 picks a free one (default: {})",
+                conf::PORT
+            );
+            println!("  -q, --quiet   do not log the requests being served");
+            println!("  --autoindex   list the contents of directories without an index.html");
+            println!(
+                "  -m, --memory  render the site in memory instead of using the output folder"
+            );
+            println!("                (changes are picked up even without --watch)");
         }
     }
 }
@@ -125,7 +147,15 @@ pub fn parse() -> Config {
     let mut write = false;
     let mut force = false;
     let mut ignore_errors = false;
+    let mut gzip = false;
     let mut watch = false;
+    let mut poll = false;
+    let mut host = conf::HOST.to_owned();
+    let mut port = conf::PORT;
+    let mut quiet = false;
+    let mut autoindex = false;
+    let mut memory = false;
+    let mut pending_value = Option::<&'static str>::None;
 
     for argument in env::args().skip(1).flat_map(parse_arg) {
         let shortcircuit_help = match &argument {
@@ -138,6 +168,32 @@ pub fn parse() -> Config {
             process::exit(0);
         }
 
+        if let Some(option) = pending_value.take() {
+            let value = match argument {
+                Arg::Value(x) => x,
+                _ => {
+                    print_usage(subcommand);
+                    println!("site: error: argument --{option}: expected one argument");
+                    process::exit(1);
+                }
+            };
+            match option {
+                "host" => host = value,
+                "port" => {
+                    port = match value.parse() {
+                        Ok(x) => x,
+                        Err(_) => {
+                            print_usage(subcommand);
+                            println!("site: error: argument --port: invalid port value: '{value}'");
+                            process::exit(1);
+                        }
+                    }
+                }
+                _ => unreachable!(),
+            }
+            continue;
+        }
+
         match subcommand {
             None => match argument {
                 Arg::Value(x) if x == "build" => {
@@ -164,6 +220,8 @@ pub fn parse() -> Config {
                 Arg::Short('f') => force = true,
                 Arg::Long(x) if x == "force" => force = true,
                 Arg::Long(x) if x == "ignore-errors" => ignore_errors = true,
+                Arg::Short('z') => gzip = true,
+                Arg::Long(x) if x == "gzip" => gzip = true,
                 arg => {
                     print_usage(subcommand);
                     println!("site: error: unrecognized arguments: {arg}");
@@ -178,6 +236,14 @@ pub fn parse() -> Config {
             Some(Subcommand::Serve) => match argument {
                 Arg::Short('w') => watch = true,
                 Arg::Long(x) if x == "watch" => watch = true,
+                Arg::Long(x) if x == "poll" => poll = true,
+                Arg::Short('q') => quiet = true,
+                Arg::Long(x) if x == "quiet" => quiet = true,
+                Arg::Long(x) if x == "autoindex" => autoindex = true,
+                Arg::Short('m') => memory = true,
+                Arg::Long(x) if x == "memory" => memory = true,
+                Arg::Long(x) if x == "host" => pending_value = Some("host"),
+                Arg::Long(x) if x == "port" => pending_value = Some("port"),
                 arg => {
                     print_usage(subcommand);
                     println!("site: error: unrecognized arguments: {arg}");
@@ -187,6 +253,12 @@ pub fn parse() -> Config {
         }
     }
 
+    if let Some(option) = pending_value {
+        print_usage(subcommand);
+        println!("site: error: argument --{option}: expected one argument");
+        process::exit(1);
+    }
+
     match subcommand {
         None => {
             print_usage(subcommand);
@@ -197,10 +269,19 @@ pub fn parse() -> Config {
             write,
             force,
             ignore_errors,
+            gzip,
             output_folder: PathBuf::from(conf::OUTPUT_FOLDER),
         }),
         Some(Subcommand::Deploy) => Config::Deploy,
-        Some(Subcommand::Serve) => Config::Serve(ServeConfig { watch }),
+        Some(Subcommand::Serve) => Config::Serve(ServeConfig {
+            watch,
+            poll,
+            host,
+            port,
+            quiet,
+            autoindex,
+            memory,
+        }),
     }
 }
 
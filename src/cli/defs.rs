@@ -10,9 +10,16 @@ pub struct BuildConfig {
     pub write: bool,
     pub force: bool,
     pub ignore_errors: bool,
+    pub gzip: bool,
     pub output_folder: PathBuf,
 }
 
 pub struct ServeConfig {
     pub watch: bool,
+    pub poll: bool,
+    pub host: String,
+    pub port: u16,
+    pub quiet: bool,
+    pub autoindex: bool,
+    pub memory: bool,
 }
@@ -1,4 +1,6 @@
 pub const CNAME: &str = "lonami.dev";
+pub const HOST: &str = "127.0.0.1";
 pub const INPUT_FOLDER: &str = "content";
 pub const OUTPUT_FOLDER: &str = "www";
+pub const PORT: u16 = 8080;
 pub const TEMPLATE_NAME: &str = "base.template.html";
@@ -1,4 +1,4 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 static NON_LEAP_DAYS_PER_MONTH: [u8; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
 static LEAP_DAYS_PER_MONTH: [u8; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
@@ -23,14 +23,12 @@ fn days_in_year_month(year: u16, month: u8) -> u8 {
     days_in_month[month as usize]
 }
 
-pub fn system_time_to_date_string(time: SystemTime) -> String {
-    let seconds = time
-        .duration_since(UNIX_EPOCH)
-        .expect("time to be after unix epoch")
-        .as_secs();
-
-    let mut days = seconds / (24 * 60 * 60);
+static MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+static WEEKDAY_NAMES: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
 
+fn days_to_year_month_day(mut days: u64) -> (u16, u8, u64) {
     let mut year = 1970;
     loop {
         let delta = u64::from(days_in_year(year));
@@ -51,7 +49,97 @@ pub fn system_time_to_date_string(time: SystemTime) -> String {
         month = (month + 1) % 12;
     }
 
+    (year, month, days)
+}
+
+fn year_month_day_to_days(year: u16, month: u8, day: u64) -> u64 {
+    let year_days = (1970..year)
+        .map(|y| u64::from(days_in_year(y)))
+        .sum::<u64>();
+    let month_days = (0..month)
+        .map(|m| u64::from(days_in_year_month(year, m)))
+        .sum::<u64>();
+    year_days + month_days + day
+}
+
+pub fn system_time_to_date_string(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .expect("time to be after unix epoch")
+        .as_secs();
+
+    let (year, mut month, mut days) = days_to_year_month_day(seconds / (24 * 60 * 60));
+
     month += 1;
     days += 1;
     format!("{year:04}-{month:02}-{days:02}")
 }
+
+pub fn system_time_to_http_date(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .expect("time to be after unix epoch")
+        .as_secs();
+
+    let days = seconds / (24 * 60 * 60);
+    let (year, month, day) = days_to_year_month_day(days);
+    let weekday = WEEKDAY_NAMES[(days % 7) as usize];
+    let month = MONTH_NAMES[month as usize];
+    let day = day + 1;
+    let hours = seconds / (60 * 60) % 24;
+    let minutes = seconds / 60 % 60;
+    let seconds = seconds % 60;
+    format!("{weekday}, {day:02} {month} {year:04} {hours:02}:{minutes:02}:{seconds:02} GMT")
+}
+
+pub fn http_date_to_system_time(date: &str) -> Option<SystemTime> {
+    // Only the preferred IMF-fixdate format is supported, e.g. "Sun, 06 Nov 1994 08:49:37 GMT".
+    let mut parts = date.split_ascii_whitespace();
+    let _weekday = parts.next()?;
+    let day = parts.next()?.parse::<u64>().ok()?;
+    let month = parts.next()?;
+    let month = MONTH_NAMES.iter().position(|&m| m == month)? as u8;
+    let year = parts.next()?.parse::<u16>().ok()?;
+    let mut time = parts.next()?.split(':').map(|t| t.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" || year < 1970 || day == 0 {
+        return None;
+    }
+
+    let days = year_month_day_to_days(year, month, day - 1);
+    let seconds = ((days * 24 + hours) * 60 + minutes) * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(
+            system_time_to_http_date(time),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+        assert_eq!(
+            http_date_to_system_time("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(time)
+        );
+
+        let time = UNIX_EPOCH + Duration::from_secs(951782400);
+        assert_eq!(
+            system_time_to_http_date(time),
+            "Tue, 29 Feb 2000 00:00:00 GMT"
+        );
+        assert_eq!(
+            http_date_to_system_time("Tue, 29 Feb 2000 00:00:00 GMT"),
+            Some(time)
+        );
+
+        assert_eq!(
+            http_date_to_system_time("Sunday, 06-Nov-94 08:49:37 GMT"),
+            None
+        );
+    }
+}
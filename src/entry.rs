@@ -1,5 +1,6 @@
+use std::any::Any;
 use std::path::{Path, PathBuf};
-use std::{fs, io};
+use std::{fmt, fs, io, panic};
 
 use crate::{conf, css, date, html, markdown, toml};
 
@@ -17,47 +18,90 @@ pub struct Entry {
     pub tags: Vec<String>,
 }
 
-fn meta_string(value: &[u8]) -> String {
-    String::from_utf8(value.to_vec()).expect("string to be valid utf8")
+// The line is 1-based, or 0 when the error cannot be traced back to a single line.
+pub struct Error {
+    pub line: usize,
+    pub message: String,
 }
 
-fn meta_date(value: &[u8]) -> String {
+// Returns the 1-based line where `part`, which must be a slice of `text`, starts.
+fn line_of(text: &[u8], part: &[u8]) -> usize {
+    let offset = (part.as_ptr() as usize).saturating_sub(text.as_ptr() as usize);
+    text[..offset.min(text.len())]
+        .iter()
+        .filter(|&&c| c == b'\n')
+        .count()
+        + 1
+}
+
+fn meta_string(text: &[u8], value: &[u8]) -> Result<String, Error> {
+    String::from_utf8(value.to_vec()).map_err(|_| Error {
+        line: line_of(text, value),
+        message: String::from("metadata is not valid utf8"),
+    })
+}
+
+fn meta_date(text: &[u8], value: &[u8]) -> Result<String, Error> {
     const YMD_FMT: &[u8] = b"YYYY-MM-DD";
-    meta_string(&value[..YMD_FMT.len().min(value.len())])
+    let date = &value[..YMD_FMT.len().min(value.len())];
+    let valid = date.is_empty()
+        || (date.len() == YMD_FMT.len()
+            && date.iter().zip(YMD_FMT).all(|(&c, &f)| {
+                if f == b'-' {
+                    c == b'-'
+                } else {
+                    c.is_ascii_digit()
+                }
+            }));
+    if !valid {
+        return Err(Error {
+            line: line_of(text, value),
+            message: String::from("date is not formatted as YYYY-MM-DD"),
+        });
+    }
+    meta_string(text, date)
 }
 
-fn from_markdown(path: PathBuf, contents: Vec<u8>) -> Entry {
+fn from_markdown(path: PathBuf, contents: Vec<u8>) -> Result<Entry, Error> {
     let mut entry = from_existing_path(path, contents);
     let mut next_is_title = false;
+    let text = &entry.processed_contents;
 
-    for token in markdown::lex(&entry.processed_contents) {
+    for token in markdown::lex(text) {
         match token {
             markdown::Token::Meta(meta) => {
-                let meta = toml::parse(meta);
-                entry.date = meta
-                    .get(&b"date"[..])
-                    .map(|v| meta_date(v[0]))
-                    .unwrap_or(entry.date);
-                entry.updated = meta.get(&b"updated"[..]).map(|v| meta_date(v[0]));
-                entry.category = meta.get(&b"category"[..]).map(|v| meta_string(v[0]));
+                let meta = toml::parse(meta).map_err(|line| Error {
+                    line: line_of(text, line),
+                    message: String::from("metadata line is not a key = value pair"),
+                })?;
+                if let Some(date) = meta.get(&b"date"[..]) {
+                    entry.date = meta_date(text, date[0])?;
+                }
+                entry.updated = meta
+                    .get(&b"updated"[..])
+                    .map(|v| meta_date(text, v[0]))
+                    .transpose()?;
+                entry.category = meta
+                    .get(&b"category"[..])
+                    .map(|v| meta_string(text, v[0]))
+                    .transpose()?;
                 entry.tags = meta
                     .get(&b"tags"[..])
                     .unwrap_or(&Vec::new())
                     .iter()
-                    .copied()
-                    .map(meta_string)
-                    .collect();
+                    .map(|v| meta_string(text, v))
+                    .collect::<Result<_, _>>()?;
 
                 if let Some(title) = meta.get(&b"title"[..]) {
-                    entry.title = meta_string(title[0]);
+                    entry.title = meta_string(text, title[0])?;
                     break;
                 }
             }
             markdown::Token::Heading(1) => {
                 next_is_title = true;
             }
-            markdown::Token::Text(text) if next_is_title => {
-                entry.title = meta_string(text);
+            markdown::Token::Text(title) if next_is_title => {
+                entry.title = meta_string(text, title)?;
                 break;
             }
             _ => continue,
@@ -72,14 +116,10 @@ fn from_markdown(path: PathBuf, contents: Vec<u8>) -> Entry {
     const MD_EXT: &str = ".md";
     let truncate = if entry.permalink.ends_with(INDEX_LISTING) {
         entry.append_siblings_listing = true;
-        entry.processed_path.set_file_name("index.html");
         INDEX_LISTING.len() - 1
     } else if entry.permalink.ends_with(INDEX_NESTED) {
-        entry.processed_path.set_extension("html");
         INDEX_NESTED.len() - 1
     } else {
-        entry.processed_path.set_extension("");
-        entry.processed_path.push("index.html");
         MD_EXT.len()
     };
     entry.permalink.truncate(entry.permalink.len() - truncate);
@@ -87,7 +127,7 @@ fn from_markdown(path: PathBuf, contents: Vec<u8>) -> Entry {
         entry.permalink.push('/');
     }
 
-    entry
+    Ok(entry)
 }
 
 fn from_css(path: PathBuf, contents: Vec<u8>) -> Entry {
@@ -131,20 +171,17 @@ fn from_new_path(path: PathBuf, contents: Vec<u8>) -> Entry {
     .to_string_lossy()
     .into_owned();
 
-    let processed_path = path
-        .strip_prefix(conf::INPUT_FOLDER)
-        .unwrap_or(&path)
-        .to_owned();
+    let relative_path = path.strip_prefix(conf::INPUT_FOLDER).unwrap_or(&path);
 
-    let mut permalink = processed_path
+    let mut permalink = relative_path
         .to_str()
         .expect("path to be stringifiable")
         .replace('\\', "/");
     permalink.replace_range(..0, "/");
 
     Entry {
+        processed_path: Entry::processed_path_of(&path),
         path,
-        processed_path,
         processed_contents: contents,
         append_css_style: Vec::new(),
         append_siblings_listing: false,
@@ -157,26 +194,110 @@ fn from_new_path(path: PathBuf, contents: Vec<u8>) -> Entry {
     }
 }
 
+impl Error {
+    pub fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload
+                .downcast_ref::<&str>()
+                .map_or_else(|| String::from("unknown panic"), |m| (*m).to_owned()),
+        };
+        Self { line: 0, message }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self {
+            line: 0,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
 impl Entry {
-    pub fn load_from_path(path: PathBuf) -> io::Result<Self> {
+    pub fn load_from_path(path: PathBuf) -> Result<Self, Error> {
         let contents = fs::read(&path)?;
-        Ok(match path.extension().and_then(|e| e.to_str()) {
+        // A bad input should be reported like any other error, not take down the watcher.
+        panic::catch_unwind(|| match path.extension().and_then(|e| e.to_str()) {
             Some("md") => from_markdown(path, contents),
-            Some("css") => from_css(path, contents),
-            Some("html") => from_html(path, contents),
-            _ => from_existing_path(path, contents),
+            Some("css") => Ok(from_css(path, contents)),
+            Some("html") => Ok(from_html(path, contents)),
+            _ => Ok(from_existing_path(path, contents)),
         })
+        .unwrap_or_else(|payload| Err(Error::from_panic(payload)))
     }
 
     pub fn from_new_path_with_contents(path: PathBuf, contents: Vec<u8>) -> Self {
         from_new_path(path, contents)
     }
 
+    pub fn processed_path_of(path: &Path) -> PathBuf {
+        let mut processed_path = path
+            .strip_prefix(conf::INPUT_FOLDER)
+            .unwrap_or(path)
+            .to_owned();
+
+        if processed_path.extension().is_some_and(|e| e == "md") {
+            match processed_path.file_stem() {
+                Some(stem) if stem == "_index" => processed_path.set_file_name("index.html"),
+                Some(stem) if stem == "index" => {
+                    processed_path.set_extension("html");
+                }
+                _ => {
+                    processed_path.set_extension("");
+                    processed_path.push("index.html");
+                }
+            }
+        }
+
+        processed_path
+    }
+
     pub fn path_parent(&self) -> Option<&Path> {
-        if self.path.file_stem().is_some_and(|s| s == "index") {
-            self.path.parent().and_then(|p| p.parent())
+        Self::path_parent_of(&self.path)
+    }
+
+    pub fn path_parent_of(path: &Path) -> Option<&Path> {
+        if path.file_stem().is_some_and(|s| s == "index") {
+            path.parent().and_then(|p| p.parent())
         } else {
-            self.path.parent()
+            path.parent()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_errors() {
+        let text = b"+++\ntitle = \"ok\"\ntags = [\"\xff\"]\n+++\n";
+        let tags = &text[26..27];
+        assert_eq!(line_of(text, &text[..0]), 1);
+        assert_eq!(line_of(text, tags), 3);
+        assert_eq!(meta_string(text, &text[13..15]).ok().as_deref(), Some("ok"));
+
+        let error = meta_string(text, tags).expect_err("invalid utf8 to fail");
+        assert_eq!(error.to_string(), "line 3: metadata is not valid utf8");
+
+        let text = b"+++\ndate = 2024-1-01\n+++\n";
+        assert_eq!(meta_date(text, &text[11..11]).ok().as_deref(), Some(""));
+        let error = meta_date(text, &text[11..20]).expect_err("short month to fail");
+        assert_eq!(
+            error.to_string(),
+            "line 2: date is not formatted as YYYY-MM-DD"
+        );
+    }
+}
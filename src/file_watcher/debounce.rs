@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use super::Event;
+
+const QUIET_PERIOD: Duration = Duration::from_millis(100);
+
+pub fn collect(receiver: &mpsc::Receiver<Event>) -> Option<Vec<Event>> {
+    let mut pending = Vec::new();
+    coalesce(&mut pending, receiver.recv().ok()?);
+    // Editors often produce several events for a single save, so wait for things to settle.
+    while let Ok(event) = receiver.recv_timeout(QUIET_PERIOD) {
+        coalesce(&mut pending, event);
+    }
+    Some(pending)
+}
+
+fn push_removed(pending: &mut Vec<Event>, path: PathBuf) {
+    // Moved away and then replaced by a new file, which is how some editors save.
+    match pending
+        .iter()
+        .position(|e| matches!(e, Event::Created(p) if *p == path))
+    {
+        Some(i) => pending[i] = Event::Modified(path),
+        None => pending.push(Event::Removed(path)),
+    }
+}
+
+fn coalesce(pending: &mut Vec<Event>, event: Event) {
+    match event {
+        Event::Created(path) => {
+            if let Some(i) = pending
+                .iter()
+                .position(|e| matches!(e, Event::Removed(p) if *p == path))
+            {
+                pending[i] = Event::Modified(path);
+            } else if !pending
+                .iter()
+                .any(|e| matches!(e, Event::Created(p) | Event::Modified(p) if *p == path))
+            {
+                pending.push(Event::Created(path));
+            }
+        }
+        Event::Modified(path) => {
+            if !pending
+                .iter()
+                .any(|e| matches!(e, Event::Created(p) | Event::Modified(p) if *p == path))
+            {
+                pending.push(Event::Modified(path));
+            }
+        }
+        Event::Removed(path) => {
+            let i = pending.iter().position(|e| match e {
+                Event::Created(p) | Event::Modified(p) => *p == path,
+                Event::Renamed { to, .. } => *to == path,
+                Event::Removed(_) => false,
+            });
+            match i.map(|i| pending.remove(i)) {
+                Some(Event::Created(_)) => {}
+                Some(Event::Modified(_)) | None => push_removed(pending, path),
+                Some(Event::Renamed { from, .. }) => push_removed(pending, from),
+                Some(Event::Removed(_)) => unreachable!(),
+            }
+        }
+        Event::Renamed { from, to } => {
+            let i = pending.iter().position(|e| match e {
+                Event::Created(p) | Event::Modified(p) => *p == from,
+                Event::Renamed { to, .. } => *to == from,
+                Event::Removed(_) => false,
+            });
+            match i.map(|i| pending.remove(i)) {
+                Some(Event::Created(_)) => coalesce(pending, Event::Created(to)),
+                Some(Event::Modified(_)) => {
+                    coalesce(pending, Event::Removed(from));
+                    coalesce(pending, Event::Created(to));
+                }
+                Some(Event::Renamed { from: origin, .. }) => {
+                    if origin != to {
+                        pending.push(Event::Renamed { from: origin, to });
+                    }
+                }
+                None => pending.push(Event::Renamed { from, to }),
+                Some(Event::Removed(_)) => unreachable!(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coalesce_all(events: Vec<Event>) -> Vec<Event> {
+        let mut pending = Vec::new();
+        for event in events {
+            coalesce(&mut pending, event);
+        }
+        pending
+    }
+
+    #[test]
+    fn test_coalesce_write_in_place() {
+        let file = PathBuf::from("file");
+        assert_eq!(
+            coalesce_all(vec![
+                Event::Modified(file.clone()),
+                Event::Modified(file.clone()),
+            ]),
+            vec![Event::Modified(file.clone())]
+        );
+        assert_eq!(
+            coalesce_all(vec![
+                Event::Created(file.clone()),
+                Event::Modified(file.clone()),
+            ]),
+            vec![Event::Created(file.clone())]
+        );
+        assert_eq!(
+            coalesce_all(vec![
+                Event::Created(file.clone()),
+                Event::Modified(file.clone()),
+                Event::Removed(file.clone()),
+            ]),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_backup_and_replace() {
+        let file = PathBuf::from("file");
+        let backup = PathBuf::from("file~");
+        assert_eq!(
+            coalesce_all(vec![
+                Event::Renamed {
+                    from: file.clone(),
+                    to: backup.clone(),
+                },
+                Event::Created(file.clone()),
+                Event::Modified(file.clone()),
+                Event::Removed(backup.clone()),
+            ]),
+            vec![Event::Modified(file.clone())]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_write_temporary_and_rename() {
+        let file = PathBuf::from("file");
+        let temporary = PathBuf::from(".file.swp");
+        assert_eq!(
+            coalesce_all(vec![
+                Event::Created(temporary.clone()),
+                Event::Modified(temporary.clone()),
+                Event::Renamed {
+                    from: temporary.clone(),
+                    to: file.clone(),
+                },
+            ]),
+            vec![Event::Created(file.clone())]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_renames() {
+        let a = PathBuf::from("a");
+        let b = PathBuf::from("b");
+        let c = PathBuf::from("c");
+        assert_eq!(
+            coalesce_all(vec![
+                Event::Renamed {
+                    from: a.clone(),
+                    to: b.clone(),
+                },
+                Event::Renamed {
+                    from: b.clone(),
+                    to: c.clone(),
+                },
+            ]),
+            vec![Event::Renamed {
+                from: a.clone(),
+                to: c.clone(),
+            }]
+        );
+        assert_eq!(
+            coalesce_all(vec![
+                Event::Renamed {
+                    from: a.clone(),
+                    to: b.clone(),
+                },
+                Event::Renamed {
+                    from: b.clone(),
+                    to: a.clone(),
+                },
+            ]),
+            vec![]
+        );
+        assert_eq!(
+            coalesce_all(vec![
+                Event::Modified(a.clone()),
+                Event::Renamed {
+                    from: a.clone(),
+                    to: b.clone(),
+                },
+            ]),
+            vec![Event::Removed(a.clone()), Event::Created(b.clone())]
+        );
+    }
+}
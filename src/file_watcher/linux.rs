@@ -0,0 +1,295 @@
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{CStr, CString, OsStr};
+use std::os::unix::ffi::OsStrExt as _;
+use std::path::{Path, PathBuf};
+use std::{fs, io, mem, process, ptr};
+
+use super::Event;
+use crate::{libc, walkdir};
+
+const WATCH_MASK: u32 = libc::IN_CLOSE_WRITE
+    | libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_ONLYDIR;
+
+fn exit_with_error(error: io::Error) -> ! {
+    eprintln!("fatal ffi error: {error}");
+    process::exit(1);
+}
+
+fn add_watch(fd: i32, dir: &Path, watches: &mut HashMap<i32, PathBuf>) -> io::Result<()> {
+    let dir_name = CString::new(dir.as_os_str().as_bytes()).expect("path to not contain nul");
+
+    let wd = unsafe { libc::inotify_add_watch(fd, dir_name.as_ptr(), WATCH_MASK) };
+    if wd < 0 {
+        let error = io::Error::last_os_error();
+        // The directory may have been removed before we got to watch it.
+        if error.kind() == io::ErrorKind::NotFound {
+            return Ok(());
+        }
+        return Err(error);
+    }
+    watches.insert(wd, dir.to_owned());
+
+    if let Ok(read_dir) = fs::read_dir(dir) {
+        for entry in read_dir.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                add_watch(fd, &entry.path(), watches)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_events(fd: i32, buffer: &mut Vec<u8>) {
+    loop {
+        let read = unsafe { libc::read(fd, buffer.as_mut_ptr() as _, buffer.capacity()) };
+        if read >= 0 {
+            unsafe {
+                buffer.set_len(read as _);
+            }
+            return;
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            exit_with_error(error);
+        }
+    }
+}
+
+fn parse_inotify_events(buffer: &[u8], result: &mut Vec<(i32, u32, u32, PathBuf)>) {
+    let mut offset = 0;
+    while offset + size_of::<libc::inotify_event>() <= buffer.len() {
+        let inotify_event = unsafe {
+            ptr::read_unaligned(buffer.as_ptr().add(offset) as *const libc::inotify_event)
+        };
+
+        let name_start = offset + size_of::<libc::inotify_event>();
+        let name_end = name_start + inotify_event.len as usize;
+        let file_name = CStr::from_bytes_until_nul(&buffer[name_start..name_end])
+            .map(|name| name.to_bytes())
+            .unwrap_or(&buffer[name_start..name_end]);
+        result.push((
+            inotify_event.wd,
+            inotify_event.mask,
+            inotify_event.cookie,
+            PathBuf::from(OsStr::from_bytes(file_name)),
+        ));
+
+        offset = name_end;
+    }
+}
+
+pub struct DirectoryWatcher {
+    root: PathBuf,
+    fd: i32,
+    watches: HashMap<i32, PathBuf>,
+    buffer: Vec<u8>,
+    events: Vec<(i32, u32, u32, PathBuf)>,
+    result: VecDeque<Event>,
+}
+
+impl DirectoryWatcher {
+    fn watch_created_dir(&mut self, dir: &Path) {
+        if let Err(error) = add_watch(self.fd, dir, &mut self.watches) {
+            exit_with_error(error);
+        }
+    }
+
+    fn unwatch_moved_dir(&mut self, dir: &Path) {
+        self.watches.retain(|&wd, path| {
+            if path.starts_with(dir) {
+                unsafe {
+                    libc::inotify_rm_watch(self.fd, wd);
+                }
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    fn process_events(&mut self) {
+        let mut moved_from = Option::<(u32, PathBuf, bool)>::None;
+
+        for (wd, mask, cookie, file_name) in mem::take(&mut self.events) {
+            if mask & libc::IN_Q_OVERFLOW != 0 {
+                // Events were lost, so everything may have changed.
+                self.result.extend(
+                    walkdir::walk(self.root.clone()).map(|entry| Event::Modified(entry.path())),
+                );
+                continue;
+            }
+
+            let dir = match self.watches.get(&wd) {
+                Some(dir) => dir,
+                None => continue,
+            };
+
+            if mask & (libc::IN_IGNORED | libc::IN_DELETE_SELF) != 0 {
+                if mask & libc::IN_IGNORED != 0 {
+                    self.watches.remove(&wd);
+                }
+                continue;
+            }
+
+            let path = dir.join(file_name);
+            let is_dir = mask & libc::IN_ISDIR != 0;
+
+            if mask & libc::IN_MOVED_TO != 0
+                && let Some((_, from, _)) = moved_from.take_if(|(c, _, _)| *c == cookie)
+            {
+                if is_dir {
+                    // Re-adding the watches updates the paths they map to.
+                    self.watch_created_dir(&path);
+                }
+                self.result.push_back(Event::Renamed { from, to: path });
+                continue;
+            }
+
+            if let Some((_, from, from_is_dir)) = moved_from.take() {
+                // Moved outside the watched folder.
+                if from_is_dir {
+                    self.unwatch_moved_dir(&from);
+                }
+                self.result.push_back(Event::Removed(from));
+            }
+
+            if mask & libc::IN_MOVED_FROM != 0 {
+                moved_from = Some((cookie, path, is_dir));
+            } else if mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+                if is_dir {
+                    // Files may have been created before the new directory was being watched.
+                    self.watch_created_dir(&path);
+                    if path.is_dir() {
+                        self.result.extend(
+                            walkdir::walk(path.clone()).map(|entry| Event::Created(entry.path())),
+                        );
+                    }
+                }
+                self.result.push_back(Event::Created(path));
+            } else if mask & libc::IN_CLOSE_WRITE != 0 {
+                self.result.push_back(Event::Modified(path));
+            } else if mask & libc::IN_DELETE != 0 {
+                self.result.push_back(Event::Removed(path));
+            }
+        }
+
+        if let Some((_, from, from_is_dir)) = moved_from {
+            if from_is_dir {
+                self.unwatch_moved_dir(&from);
+            }
+            self.result.push_back(Event::Removed(from));
+        }
+    }
+}
+
+impl Iterator for DirectoryWatcher {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.result.pop_front() {
+                return Some(event);
+            }
+
+            read_events(self.fd, &mut self.buffer);
+            parse_inotify_events(&self.buffer, &mut self.events);
+            self.process_events();
+        }
+    }
+}
+
+impl Drop for DirectoryWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+pub fn watch(folder: &str) -> io::Result<DirectoryWatcher> {
+    let root = PathBuf::from(folder);
+    if !root.is_dir() {
+        return Err(io::ErrorKind::NotADirectory.into());
+    }
+
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut watches = HashMap::new();
+    if let Err(error) = add_watch(fd, &root, &mut watches) {
+        unsafe {
+            libc::close(fd);
+        }
+        return Err(error);
+    }
+
+    Ok(DirectoryWatcher {
+        root,
+        fd,
+        watches,
+        buffer: Vec::with_capacity(4096),
+        events: Vec::new(),
+        result: VecDeque::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_watch() {
+        let root = env::temp_dir().join(format!("site-watcher-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut watcher = watch(root.to_str().unwrap()).unwrap();
+
+        let (a, b) = (root.join("a.md"), root.join("b.md"));
+        fs::write(&a, "one").unwrap();
+        fs::write(&a, "two").unwrap();
+        fs::rename(&a, &b).unwrap();
+        fs::remove_file(&b).unwrap();
+        // Identical events that are next to each other are merged by inotify itself.
+        assert_eq!(
+            watcher.by_ref().take(4).collect::<Vec<_>>(),
+            vec![
+                Event::Created(a.clone()),
+                Event::Modified(a.clone()),
+                Event::Renamed {
+                    from: a.clone(),
+                    to: b.clone(),
+                },
+                Event::Removed(b.clone()),
+            ]
+        );
+
+        // Directories are watched as they appear, and keep being watched after a rename.
+        let (dir, moved) = (root.join("dir"), root.join("moved"));
+        fs::create_dir(&dir).unwrap();
+        assert_eq!(watcher.next(), Some(Event::Created(dir.clone())));
+        fs::rename(&dir, &moved).unwrap();
+        fs::write(moved.join("c.md"), "").unwrap();
+        assert_eq!(
+            watcher.by_ref().take(3).collect::<Vec<_>>(),
+            vec![
+                Event::Renamed {
+                    from: dir.clone(),
+                    to: moved.clone(),
+                },
+                Event::Created(moved.join("c.md")),
+                Event::Modified(moved.join("c.md")),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
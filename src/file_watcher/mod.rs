@@ -1,11 +1,94 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+mod debounce;
+mod polling;
+
 #[cfg(target_os = "windows")]
 mod windows;
 
 #[cfg(target_os = "windows")]
-pub use windows::{DirectoryWatcher, watch};
+use windows as native;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+use linux as native;
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 mod not_windows;
 
-#[cfg(not(target_os = "windows"))]
-pub use not_windows::{DirectoryWatcher, watch};
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+use not_windows as native;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Created(PathBuf),
+    Modified(PathBuf),
+    Removed(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+enum Backend {
+    Native(native::DirectoryWatcher),
+    Polling(polling::DirectoryWatcher),
+}
+
+impl Iterator for Backend {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Native(watcher) => watcher.next(),
+            Self::Polling(watcher) => watcher.next(),
+        }
+    }
+}
+
+pub struct DirectoryWatcher {
+    receiver: mpsc::Receiver<Event>,
+    result: VecDeque<Event>,
+}
+
+impl Iterator for DirectoryWatcher {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.result.is_empty() {
+            self.result = debounce::collect(&self.receiver)?.into();
+        }
+        self.result.pop_front()
+    }
+}
+
+pub fn watch(folder: &str, force_polling: bool) -> DirectoryWatcher {
+    let mut backend = None;
+    if !force_polling {
+        match native::watch(folder) {
+            Ok(watcher) => backend = Some(Backend::Native(watcher)),
+            Err(error) => {
+                eprintln!("native file watching failed, falling back to polling: {error}")
+            }
+        }
+    }
+    let backend = backend.unwrap_or_else(|| Backend::Polling(polling::watch(folder)));
+
+    // The backends block until there are changes, so they're drained from their own thread
+    // in order to be able to wait for a quiet period before yielding events.
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for event in backend {
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    DirectoryWatcher {
+        receiver,
+        result: VecDeque::new(),
+    }
+}
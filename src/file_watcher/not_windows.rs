@@ -1,15 +1,17 @@
-use std::path::PathBuf;
+use std::io;
 
-pub struct DirectoryWatcher {}
+use super::Event;
+
+pub enum DirectoryWatcher {}
 
 impl Iterator for DirectoryWatcher {
-    type Item = PathBuf;
+    type Item = Event;
 
     fn next(&mut self) -> Option<Self::Item> {
-        unimplemented!()
+        match *self {}
     }
 }
 
-pub fn watch(folder: &str) -> DirectoryWatcher {
-    unimplemented!()
+pub fn watch(_folder: &str) -> io::Result<DirectoryWatcher> {
+    Err(io::ErrorKind::Unsupported.into())
 }
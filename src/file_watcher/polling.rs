@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use super::Event;
+use crate::walkdir;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+type Snapshot = HashMap<PathBuf, (SystemTime, u64)>;
+
+fn take_snapshot(root: &Path) -> Snapshot {
+    walkdir::walk(root.to_owned())
+        .filter_map(|entry| {
+            // The file may be gone by the time its metadata is read.
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            Some((entry.path(), (modified, metadata.len())))
+        })
+        .collect()
+}
+
+fn diff_snapshots(old: &Snapshot, new: &Snapshot, result: &mut Vec<Event>) {
+    for (path, stamp) in new {
+        match old.get(path) {
+            None => result.push(Event::Created(path.clone())),
+            Some(old_stamp) if old_stamp != stamp => result.push(Event::Modified(path.clone())),
+            Some(_) => {}
+        }
+    }
+    for path in old.keys() {
+        if !new.contains_key(path) {
+            result.push(Event::Removed(path.clone()));
+        }
+    }
+}
+
+pub struct DirectoryWatcher {
+    root: PathBuf,
+    snapshot: Snapshot,
+    result: Vec<Event>,
+}
+
+impl Iterator for DirectoryWatcher {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.result.pop() {
+                return Some(event);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+            let snapshot = take_snapshot(&self.root);
+            diff_snapshots(&self.snapshot, &snapshot, &mut self.result);
+            self.snapshot = snapshot;
+        }
+    }
+}
+
+pub fn watch(folder: &str) -> DirectoryWatcher {
+    let root = PathBuf::from(folder);
+    let snapshot = take_snapshot(&root);
+
+    DirectoryWatcher {
+        root,
+        snapshot,
+        result: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + Duration::from_secs(1);
+
+        let old = Snapshot::from([
+            (PathBuf::from("kept"), (t0, 1)),
+            (PathBuf::from("touched"), (t0, 1)),
+            (PathBuf::from("resized"), (t0, 1)),
+            (PathBuf::from("removed"), (t0, 1)),
+        ]);
+        let new = Snapshot::from([
+            (PathBuf::from("kept"), (t0, 1)),
+            (PathBuf::from("touched"), (t1, 1)),
+            (PathBuf::from("resized"), (t0, 2)),
+            (PathBuf::from("created"), (t0, 1)),
+        ]);
+
+        let mut result = Vec::new();
+        diff_snapshots(&old, &new, &mut result);
+        result.sort_by_key(|event| format!("{event:?}"));
+
+        assert_eq!(
+            result,
+            vec![
+                Event::Created(PathBuf::from("created")),
+                Event::Modified(PathBuf::from("resized")),
+                Event::Modified(PathBuf::from("touched")),
+                Event::Removed(PathBuf::from("removed")),
+            ]
+        );
+    }
+}
@@ -1,6 +1,8 @@
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
 use std::{io, process, ptr, slice};
 
+use super::Event;
 use crate::winapi;
 
 fn exit_with_last_error() {
@@ -9,8 +11,8 @@ fn exit_with_last_error() {
     process::exit(1);
 }
 
-fn open_folder(file: &Path) -> isize {
-    let file = file.canonicalize().expect("folder to exist");
+fn open_folder(file: &Path) -> io::Result<isize> {
+    let file = file.canonicalize()?;
 
     let file_name = file.to_string_lossy().encode_utf16().collect::<Vec<_>>();
 
@@ -26,9 +28,9 @@ fn open_folder(file: &Path) -> isize {
         )
     };
     if dir_handle == winapi::INVALID_HANDLE_VALUE {
-        exit_with_last_error();
+        return Err(io::Error::last_os_error());
     }
-    dir_handle
+    Ok(dir_handle)
 }
 
 fn poll_directory_change(dir_handle: isize, buffer: &mut Vec<u8>, result: &mut Vec<(i32, String)>) {
@@ -80,40 +82,76 @@ fn parse_file_notify_informations(buffer: &[u8], result: &mut Vec<(i32, String)>
         if file_notify_information.NextEntryOffset == 0 {
             break;
         }
-        offset = file_notify_information.NextEntryOffset as _;
+        offset += file_notify_information.NextEntryOffset as isize;
     }
 }
 
+const ADDED: i32 = winapi::FILE_ACTION::ADDED as i32;
+const REMOVED: i32 = winapi::FILE_ACTION::REMOVED as i32;
+const MODIFIED: i32 = winapi::FILE_ACTION::MODIFIED as i32;
+const RENAMED_OLD_NAME: i32 = winapi::FILE_ACTION::RENAMED_OLD_NAME as i32;
+const RENAMED_NEW_NAME: i32 = winapi::FILE_ACTION::RENAMED_NEW_NAME as i32;
+
 pub struct DirectoryWatcher {
     root: PathBuf,
     dir_handle: isize,
     buffer: Vec<u8>,
-    result: Vec<(i32, String)>,
+    changes: Vec<(i32, String)>,
+    result: VecDeque<Event>,
+}
+
+impl DirectoryWatcher {
+    fn process_changes(&mut self) {
+        let mut renamed_from = None;
+        for (action, file_name) in self.changes.drain(..) {
+            let path = self.root.join(file_name);
+            let event = match action {
+                ADDED => Event::Created(path),
+                REMOVED => Event::Removed(path),
+                MODIFIED => Event::Modified(path),
+                RENAMED_OLD_NAME => {
+                    renamed_from = Some(path);
+                    continue;
+                }
+                RENAMED_NEW_NAME => match renamed_from.take() {
+                    Some(from) => Event::Renamed { from, to: path },
+                    None => Event::Created(path),
+                },
+                _ => continue,
+            };
+            self.result.push_back(event);
+        }
+        if let Some(from) = renamed_from {
+            // Renamed to somewhere outside the watched folder.
+            self.result.push_back(Event::Removed(from));
+        }
+    }
 }
 
 impl Iterator for DirectoryWatcher {
-    type Item = PathBuf;
+    type Item = Event;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if let Some((_, file_name)) = self.result.pop() {
-                return Some(self.root.join(file_name));
+            if let Some(event) = self.result.pop_front() {
+                return Some(event);
             }
 
-            poll_directory_change(self.dir_handle, &mut self.buffer, &mut self.result);
-            self.result.dedup();
+            poll_directory_change(self.dir_handle, &mut self.buffer, &mut self.changes);
+            self.process_changes();
         }
     }
 }
 
-pub fn watch(folder: &str) -> DirectoryWatcher {
+pub fn watch(folder: &str) -> io::Result<DirectoryWatcher> {
     let root = PathBuf::from(folder);
-    let dir_handle = open_folder(&root);
+    let dir_handle = open_folder(&root)?;
 
-    DirectoryWatcher {
+    Ok(DirectoryWatcher {
         root,
         dir_handle,
         buffer: Vec::with_capacity(1024),
-        result: Vec::new(),
-    }
+        changes: Vec::new(),
+        result: VecDeque::new(),
+    })
 }
@@ -0,0 +1,658 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+// Searching stops after a match this long, or after following this many candidates.
+const NICE_MATCH: usize = 128;
+const MAX_CHAIN: usize = 128;
+const HASH_SIZE: usize = 1 << 15;
+const BLOCK_SYMBOLS: usize = 1 << 14;
+const MAX_STORED: usize = u16::MAX as usize;
+
+const END_OF_BLOCK: usize = 256;
+const LITERAL_LENGTH_CODES: usize = 286;
+const DISTANCE_CODES: usize = 30;
+const CODE_LENGTH_CODES: usize = 19;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; CODE_LENGTH_CODES] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+#[derive(Clone, Copy)]
+enum Symbol {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+struct BitWriter {
+    result: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+struct Block<'a> {
+    symbols: &'a [Symbol],
+    data: &'a [u8],
+    last: bool,
+}
+
+struct Codes {
+    literal_lengths: Vec<u8>,
+    distance_lengths: Vec<u8>,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            result: Vec::new(),
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, count: u8) {
+        self.bits |= (value as u64) << self.count;
+        self.count += count as u32;
+        while self.count >= 8 {
+            self.result.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes are packed starting with their most significant bit.
+    fn write_code(&mut self, code: u16, length: u8) {
+        let reversed = code.reverse_bits() >> (16 - length as u32);
+        self.write(reversed as u32, length);
+    }
+
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.result.push(self.bits as u8);
+            self.bits = 0;
+            self.count = 0;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.result
+    }
+}
+
+fn length_code(length: u16) -> usize {
+    LENGTH_BASE.partition_point(|&base| base <= length) - 1
+}
+
+fn distance_code(distance: u16) -> usize {
+    DISTANCE_BASE.partition_point(|&base| base <= distance) - 1
+}
+
+fn hash(data: &[u8]) -> usize {
+    ((data[0] as usize) << 10 ^ (data[1] as usize) << 5 ^ data[2] as usize) & (HASH_SIZE - 1)
+}
+
+fn find_symbols(data: &[u8]) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    // Chains of earlier positions with the same hash, most recent first.
+    let mut head = vec![usize::MAX; HASH_SIZE];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(&data[i..]);
+            prev[i % WINDOW_SIZE] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_length = 0;
+        let mut best_distance = 0;
+        if i + MIN_MATCH <= data.len() {
+            let max_length = MAX_MATCH.min(data.len() - i);
+            let mut candidate = head[hash(&data[i..])];
+            let mut chain = 0;
+            while candidate < i && i - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let length = data[candidate..]
+                    .iter()
+                    .zip(&data[i..i + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_distance = i - candidate;
+                    if length >= NICE_MATCH.min(max_length) {
+                        break;
+                    }
+                }
+                candidate = prev[candidate % WINDOW_SIZE];
+                chain += 1;
+            }
+        }
+
+        if best_length >= MIN_MATCH {
+            symbols.push(Symbol::Match {
+                length: best_length as u16,
+                distance: best_distance as u16,
+            });
+            for j in i..i + best_length {
+                insert(j, &mut head, &mut prev);
+            }
+            i += best_length;
+        } else {
+            symbols.push(Symbol::Literal(data[i]));
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+
+    symbols
+}
+
+// Builds a Huffman code no longer than `max_bits`, returning the length of each symbol.
+fn code_lengths(frequencies: &[u32], max_bits: u8) -> Vec<u8> {
+    let mut frequencies = frequencies.to_vec();
+    // A lone symbol still needs a complete code, so a sibling is made up for it.
+    match frequencies.iter().filter(|&&f| f > 0).count() {
+        0 => return vec![0; frequencies.len()],
+        1 => {
+            let other = if frequencies[0] == 0 { 0 } else { 1 };
+            frequencies[other] = 1;
+        }
+        _ => {}
+    }
+
+    loop {
+        let mut parents = Vec::new();
+        let mut heap = BinaryHeap::new();
+        for (symbol, &frequency) in frequencies.iter().enumerate() {
+            if frequency > 0 {
+                heap.push(Reverse((frequency as u64, symbol)));
+            }
+        }
+        let leaves = frequencies.len();
+        let mut parent_of = vec![usize::MAX; leaves];
+        while heap.len() > 1 {
+            let Reverse((a_weight, a)) = heap.pop().expect("heap to have two nodes");
+            let Reverse((b_weight, b)) = heap.pop().expect("heap to have two nodes");
+            let node = leaves + parents.len();
+            parents.push(usize::MAX);
+            for child in [a, b] {
+                if child < leaves {
+                    parent_of[child] = node;
+                } else {
+                    parents[child - leaves] = node;
+                }
+            }
+            heap.push(Reverse((a_weight + b_weight, node)));
+        }
+
+        let depth_of = |mut node: usize| {
+            let mut depth = 0;
+            while node != usize::MAX {
+                node = if node < leaves {
+                    parent_of[node]
+                } else {
+                    parents[node - leaves]
+                };
+                depth += 1;
+            }
+            depth - 1
+        };
+        let lengths = (0..leaves)
+            .map(|symbol| {
+                if frequencies[symbol] > 0 {
+                    depth_of(symbol) as u8
+                } else {
+                    0
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if lengths.iter().all(|&length| length <= max_bits) {
+            return lengths;
+        }
+        // Flattening the distribution shortens the deepest codes, at some cost in size.
+        for frequency in frequencies.iter_mut().filter(|f| **f > 0) {
+            *frequency = (*frequency / 2).max(1);
+        }
+    }
+}
+
+fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut length_count = [0u16; 16];
+    for &length in lengths {
+        length_count[length as usize] += 1;
+    }
+    length_count[0] = 0;
+
+    let mut next_code = [0u16; 16];
+    let mut code = 0;
+    for bits in 1..16 {
+        code = (code + length_count[bits - 1]) << 1;
+        next_code[bits] = code;
+    }
+
+    lengths
+        .iter()
+        .map(|&length| {
+            if length == 0 {
+                0
+            } else {
+                let code = next_code[length as usize];
+                next_code[length as usize] += 1;
+                code
+            }
+        })
+        .collect()
+}
+
+fn fixed_codes() -> Codes {
+    let mut literal_lengths = vec![8; 288];
+    literal_lengths[144..256].fill(9);
+    literal_lengths[256..280].fill(7);
+    Codes {
+        literal_lengths,
+        distance_lengths: vec![5; DISTANCE_CODES],
+    }
+}
+
+fn dynamic_codes(symbols: &[Symbol]) -> Codes {
+    let mut literal_frequencies = vec![0; LITERAL_LENGTH_CODES];
+    let mut distance_frequencies = vec![0; DISTANCE_CODES];
+    for symbol in symbols {
+        match *symbol {
+            Symbol::Literal(byte) => literal_frequencies[byte as usize] += 1,
+            Symbol::Match { length, distance } => {
+                literal_frequencies[257 + length_code(length)] += 1;
+                distance_frequencies[distance_code(distance)] += 1;
+            }
+        }
+    }
+    literal_frequencies[END_OF_BLOCK] += 1;
+    // At least one distance code must be described, even if unused.
+    if distance_frequencies.iter().all(|&f| f == 0) {
+        distance_frequencies[0] = 1;
+    }
+
+    Codes {
+        literal_lengths: code_lengths(&literal_frequencies, 15),
+        distance_lengths: code_lengths(&distance_frequencies, 15),
+    }
+}
+
+// Run-length encodes the code lengths, as (code length symbol, extra bits value).
+fn encode_lengths(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let length = lengths[i];
+        let run = lengths[i..].iter().take_while(|&&l| l == length).count();
+        if length == 0 && run >= 11 {
+            let run = run.min(138);
+            result.push((18, (run - 11) as u8));
+            i += run;
+        } else if length == 0 && run >= 3 {
+            result.push((17, (run - 3) as u8));
+            i += run;
+        } else if length != 0 && run >= 4 {
+            let run = (run - 1).min(6);
+            result.push((length, 0));
+            result.push((16, (run - 3) as u8));
+            i += run + 1;
+        } else {
+            result.push((length, 0));
+            i += 1;
+        }
+    }
+    result
+}
+
+fn code_length_extra_bits(symbol: u8) -> u8 {
+    match symbol {
+        16 => 2,
+        17 => 3,
+        18 => 7,
+        _ => 0,
+    }
+}
+
+fn symbols_cost(symbols: &[Symbol], codes: &Codes) -> usize {
+    let mut cost = codes.literal_lengths[END_OF_BLOCK] as usize;
+    for symbol in symbols {
+        cost += match *symbol {
+            Symbol::Literal(byte) => codes.literal_lengths[byte as usize] as usize,
+            Symbol::Match { length, distance } => {
+                let length_code = length_code(length);
+                let distance_code = distance_code(distance);
+                codes.literal_lengths[257 + length_code] as usize
+                    + LENGTH_EXTRA[length_code] as usize
+                    + codes.distance_lengths[distance_code] as usize
+                    + DISTANCE_EXTRA[distance_code] as usize
+            }
+        };
+    }
+    cost
+}
+
+fn write_symbols(writer: &mut BitWriter, symbols: &[Symbol], codes: &Codes) {
+    let literal_codes = canonical_codes(&codes.literal_lengths);
+    let distance_codes = canonical_codes(&codes.distance_lengths);
+    let write_literal = |writer: &mut BitWriter, symbol: usize| {
+        writer.write_code(literal_codes[symbol], codes.literal_lengths[symbol]);
+    };
+
+    for symbol in symbols {
+        match *symbol {
+            Symbol::Literal(byte) => write_literal(writer, byte as usize),
+            Symbol::Match { length, distance } => {
+                let code = length_code(length);
+                write_literal(writer, 257 + code);
+                writer.write((length - LENGTH_BASE[code]) as u32, LENGTH_EXTRA[code]);
+
+                let code = distance_code(distance);
+                writer.write_code(distance_codes[code], codes.distance_lengths[code]);
+                writer.write(
+                    (distance - DISTANCE_BASE[code]) as u32,
+                    DISTANCE_EXTRA[code],
+                );
+            }
+        }
+    }
+    write_literal(writer, END_OF_BLOCK);
+}
+
+fn write_block(writer: &mut BitWriter, block: Block) {
+    let dynamic = dynamic_codes(block.symbols);
+    let literal_count = 257.max(
+        dynamic
+            .literal_lengths
+            .iter()
+            .rposition(|&l| l != 0)
+            .map_or(0, |i| i + 1),
+    );
+    let distance_count = 1.max(
+        dynamic
+            .distance_lengths
+            .iter()
+            .rposition(|&l| l != 0)
+            .map_or(0, |i| i + 1),
+    );
+    let encoded_lengths = encode_lengths(
+        &[
+            &dynamic.literal_lengths[..literal_count],
+            &dynamic.distance_lengths[..distance_count],
+        ]
+        .concat(),
+    );
+    let mut code_length_frequencies = vec![0; CODE_LENGTH_CODES];
+    for &(symbol, _) in &encoded_lengths {
+        code_length_frequencies[symbol as usize] += 1;
+    }
+    let code_length_lengths = code_lengths(&code_length_frequencies, 7);
+    let code_length_count = 4.max(
+        CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|&symbol| code_length_lengths[symbol] != 0)
+            .map_or(0, |i| i + 1),
+    );
+
+    let dynamic_cost = 14
+        + 3 * code_length_count
+        + encoded_lengths
+            .iter()
+            .map(|&(symbol, _)| {
+                (code_length_lengths[symbol as usize] + code_length_extra_bits(symbol)) as usize
+            })
+            .sum::<usize>()
+        + symbols_cost(block.symbols, &dynamic);
+    let fixed = fixed_codes();
+    let fixed_cost = symbols_cost(block.symbols, &fixed);
+    let stored_cost = block.data.len().div_ceil(MAX_STORED).max(1) * 40 + 8 * block.data.len();
+
+    if stored_cost <= dynamic_cost.min(fixed_cost) {
+        let chunks = block.data.chunks(MAX_STORED).collect::<Vec<_>>();
+        let chunk_count = chunks.len().max(1);
+        for i in 0..chunk_count {
+            let chunk = chunks.get(i).copied().unwrap_or_default();
+            writer.write((block.last && i + 1 == chunk_count) as u32, 1);
+            writer.write(0b00, 2);
+            writer.align();
+            writer.write(chunk.len() as u32, 16);
+            writer.write(!(chunk.len() as u16) as u32, 16);
+            writer.result.extend_from_slice(chunk);
+        }
+    } else if fixed_cost <= dynamic_cost {
+        writer.write(block.last as u32, 1);
+        writer.write(0b01, 2);
+        write_symbols(writer, block.symbols, &fixed);
+    } else {
+        writer.write(block.last as u32, 1);
+        writer.write(0b10, 2);
+        writer.write((literal_count - 257) as u32, 5);
+        writer.write((distance_count - 1) as u32, 5);
+        writer.write((code_length_count - 4) as u32, 4);
+        for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+            writer.write(code_length_lengths[symbol] as u32, 3);
+        }
+        let code_length_codes = canonical_codes(&code_length_lengths);
+        for &(symbol, extra) in &encoded_lengths {
+            writer.write_code(
+                code_length_codes[symbol as usize],
+                code_length_lengths[symbol as usize],
+            );
+            writer.write(extra as u32, code_length_extra_bits(symbol));
+        }
+        write_symbols(writer, block.symbols, &dynamic);
+    }
+}
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let symbols = find_symbols(data);
+    let mut writer = BitWriter::new();
+
+    let mut start = 0;
+    let mut chunks = symbols.chunks(BLOCK_SYMBOLS).peekable();
+    if chunks.peek().is_none() {
+        write_block(
+            &mut writer,
+            Block {
+                symbols: &[],
+                data: &[],
+                last: true,
+            },
+        );
+    }
+    while let Some(symbols) = chunks.next() {
+        let length = symbols
+            .iter()
+            .map(|symbol| match *symbol {
+                Symbol::Literal(_) => 1,
+                Symbol::Match { length, .. } => length as usize,
+            })
+            .sum::<usize>();
+        write_block(
+            &mut writer,
+            Block {
+                symbols,
+                data: &data[start..start + length],
+                last: chunks.peek().is_none(),
+            },
+        );
+        start += length;
+    }
+
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct BitReader<'a> {
+        data: &'a [u8],
+        position: usize,
+    }
+
+    impl BitReader<'_> {
+        fn read(&mut self, count: u8) -> u32 {
+            let mut value = 0;
+            for i in 0..count {
+                let bit = self.data[self.position / 8] >> (self.position % 8) & 1;
+                value |= (bit as u32) << i;
+                self.position += 1;
+            }
+            value
+        }
+
+        fn read_symbol(&mut self, lengths: &[u8]) -> usize {
+            let codes = canonical_codes(lengths);
+            let (mut code, mut length) = (0, 0);
+            loop {
+                code = code << 1 | self.read(1) as u16;
+                length += 1;
+                if let Some(symbol) = (0..lengths.len())
+                    .find(|&symbol| lengths[symbol] == length && codes[symbol] == code)
+                {
+                    return symbol;
+                }
+            }
+        }
+    }
+
+    // A slow but straightforward decoder, only good enough to check the encoder's output.
+    fn inflate(data: &[u8]) -> Vec<u8> {
+        let mut reader = BitReader { data, position: 0 };
+        let mut result = Vec::<u8>::new();
+        loop {
+            let last = reader.read(1) == 1;
+            let codes = match reader.read(2) {
+                0b00 => {
+                    reader.position = reader.position.div_ceil(8) * 8;
+                    let length = reader.read(16) as usize;
+                    assert_eq!(reader.read(16) as usize, !length & 0xffff);
+                    let start = reader.position / 8;
+                    result.extend_from_slice(&data[start..start + length]);
+                    reader.position += 8 * length;
+                    None
+                }
+                0b01 => Some(fixed_codes()),
+                0b10 => {
+                    let literal_count = reader.read(5) as usize + 257;
+                    let distance_count = reader.read(5) as usize + 1;
+                    let code_length_count = reader.read(4) as usize + 4;
+                    let mut code_length_lengths = [0; CODE_LENGTH_CODES];
+                    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+                        code_length_lengths[symbol] = reader.read(3) as u8;
+                    }
+                    let mut lengths = Vec::new();
+                    while lengths.len() < literal_count + distance_count {
+                        match reader.read_symbol(&code_length_lengths) {
+                            16 => {
+                                let last = *lengths.last().unwrap();
+                                lengths.extend(vec![last; 3 + reader.read(2) as usize]);
+                            }
+                            17 => lengths.extend(vec![0; 3 + reader.read(3) as usize]),
+                            18 => lengths.extend(vec![0; 11 + reader.read(7) as usize]),
+                            length => lengths.push(length as u8),
+                        }
+                    }
+                    Some(Codes {
+                        distance_lengths: lengths.split_off(literal_count),
+                        literal_lengths: lengths,
+                    })
+                }
+                _ => panic!("block type to be valid"),
+            };
+
+            if let Some(codes) = codes {
+                loop {
+                    let symbol = reader.read_symbol(&codes.literal_lengths);
+                    if symbol < 256 {
+                        result.push(symbol as u8);
+                    } else if symbol == END_OF_BLOCK {
+                        break;
+                    } else {
+                        let code = symbol - 257;
+                        let length =
+                            LENGTH_BASE[code] as usize + reader.read(LENGTH_EXTRA[code]) as usize;
+                        let code = reader.read_symbol(&codes.distance_lengths);
+                        let distance = DISTANCE_BASE[code] as usize
+                            + reader.read(DISTANCE_EXTRA[code]) as usize;
+                        for _ in 0..length {
+                            result.push(result[result.len() - distance]);
+                        }
+                    }
+                }
+            }
+
+            if last {
+                return result;
+            }
+        }
+    }
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(length_code(3), 0);
+        assert_eq!(length_code(10), 7);
+        assert_eq!(length_code(11), 8);
+        assert_eq!(length_code(257), 27);
+        assert_eq!(length_code(258), 28);
+        assert_eq!(distance_code(1), 0);
+        assert_eq!(distance_code(6), 4);
+        assert_eq!(distance_code(32768), 29);
+
+        assert_eq!(
+            canonical_codes(&[2, 1, 3, 3]),
+            vec![0b10, 0b0, 0b110, 0b111]
+        );
+        let lengths = code_lengths(&[1, 1, 2, 4, 8, 16, 32, 64, 128, 256, 512], 5);
+        assert!(lengths.iter().all(|&l| (1..=5).contains(&l)));
+        assert_eq!(code_lengths(&[0, 7, 0], 15), vec![1, 1, 0]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut noise = Vec::new();
+        let mut state = 1u32;
+        for _ in 0..100_000 {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            noise.push((state >> 16) as u8);
+        }
+        let text = "<p>The quick brown fox jumps over the lazy dog.</p>\n".repeat(2000);
+
+        for data in [
+            &b""[..],
+            b"a",
+            b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+            b"abcabcabcabd",
+            text.as_bytes(),
+            &noise,
+        ] {
+            let compressed = compress(data);
+            assert_eq!(inflate(&compressed), data);
+        }
+
+        assert!(compress(text.as_bytes()).len() < text.len() / 20);
+        assert!(compress(&noise).len() < noise.len() + noise.len() / 100);
+    }
+}
@@ -0,0 +1,62 @@
+mod deflate;
+
+pub use deflate::compress;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+// Wraps the DEFLATE stream in a gzip member with no name nor modification time,
+// so that the same input always produces the same output.
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    const HEADER: [u8; 10] = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+
+    let mut result = HEADER.to_vec();
+    result.extend_from_slice(&compress(data));
+    result.extend_from_slice(&crc32(data).to_le_bytes());
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    }
+
+    #[test]
+    fn test_encode() {
+        let encoded = encode(b"hello");
+        assert_eq!(encoded[..3], [0x1f, 0x8b, 8]);
+        assert_eq!(
+            encoded[encoded.len() - 8..],
+            [0x86, 0xa6, 0x10, 0x36, 5, 0, 0, 0]
+        );
+    }
+}
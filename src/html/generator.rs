@@ -1,9 +1,10 @@
 use std::borrow::Cow;
 
+use super::highlight::highlight;
 use super::{escape, escape_attribute};
 use crate::collections::{Graph, GraphNodeRef as Ref};
 use crate::html;
-use crate::markdown::Node;
+use crate::markdown::{Alignment, Node};
 
 pub fn generate(arena: Graph<Node>) -> Vec<u8> {
     let mut result = Vec::new();
@@ -15,7 +16,10 @@ fn visit(cursor: Ref<Node>, buffer: &mut Vec<u8>) {
     match cursor.value() {
         Node::Empty => {}
         Node::Raw(text) => buffer.extend_from_slice(text),
-        Node::Text(text) => buffer.extend_from_slice(&escape(text)),
+        Node::Text(text) => match cursor.parent().map(|parent| parent.value()) {
+            Some(Node::Pre(lang)) => buffer.extend_from_slice(&highlight(lang, text)),
+            _ => buffer.extend_from_slice(&escape(text)),
+        },
         Node::AltText(_) => {
             return; // processed earlier
         }
@@ -26,7 +30,18 @@ fn visit(cursor: Ref<Node>, buffer: &mut Vec<u8>) {
             buffer.extend_from_slice(if ordered { b"<ol>" } else { b"<ul>" })
         }
         Node::ListItem => {
-            buffer.extend_from_slice(b"<li>");
+            buffer.extend_from_slice(if is_task(cursor) {
+                b"<li class=\"task-list-item\">"
+            } else {
+                b"<li>"
+            });
+        }
+        Node::Task { checked } => {
+            buffer.extend_from_slice(if checked {
+                b"<input type=\"checkbox\" disabled checked>"
+            } else {
+                b"<input type=\"checkbox\" disabled>"
+            });
         }
         Node::DefinitionItem(identifier) => {
             if !identifier.starts_with(b"^") {
@@ -57,8 +72,9 @@ fn visit(cursor: Ref<Node>, buffer: &mut Vec<u8>) {
             buffer.extend_from_slice("\">↪".as_bytes());
             buffer.extend_from_slice(identifier);
         }
-        Node::Reference(url) => {
+        Node::Reference { scheme, url } => {
             buffer.extend_from_slice(b"<a href=\"");
+            buffer.extend_from_slice(scheme);
             buffer.extend_from_slice(url);
             buffer.extend_from_slice(b"\"");
             if let Some(Node::AltText(alt)) = cursor.last_child().map(|child| child.value()) {
@@ -137,6 +153,22 @@ fn visit(cursor: Ref<Node>, buffer: &mut Vec<u8>) {
         Node::Quote => {
             buffer.extend_from_slice(b"<blockquote>");
         }
+        Node::Table => {
+            buffer.extend_from_slice(b"<table>");
+        }
+        Node::TableRow => {
+            buffer.extend_from_slice(b"<tr>");
+        }
+        Node::TableCell { header, alignment } => {
+            buffer.extend_from_slice(if header { b"<th" } else { b"<td" });
+            buffer.extend_from_slice(match alignment {
+                Alignment::None => b"",
+                Alignment::Left => b" style=\"text-align:left\"".as_ref(),
+                Alignment::Center => b" style=\"text-align:center\"",
+                Alignment::Right => b" style=\"text-align:right\"",
+            });
+            buffer.push(b'>');
+        }
     }
     for child in cursor.children() {
         visit(child, buffer);
@@ -155,6 +187,7 @@ fn visit(cursor: Ref<Node>, buffer: &mut Vec<u8>) {
         Node::ListItem => {
             buffer.extend_from_slice(b"</li>");
         }
+        Node::Task { .. } => {}
         Node::DefinitionItem(identifier) => {
             buffer.extend_from_slice(b"&nbsp;<a href=\"#fnref:");
             buffer.extend_from_slice(&identifier[1..]);
@@ -174,7 +207,7 @@ fn visit(cursor: Ref<Node>, buffer: &mut Vec<u8>) {
         Node::FootnoteReference(_) => {
             buffer.extend_from_slice(b"</sup></a>");
         }
-        Node::Reference(_) => {
+        Node::Reference { .. } => {
             buffer.extend_from_slice(b"</a>");
         }
         Node::Image(_) => {}
@@ -202,12 +235,39 @@ fn visit(cursor: Ref<Node>, buffer: &mut Vec<u8>) {
         Node::Quote => {
             buffer.extend_from_slice(b"</blockquote>");
         }
+        Node::Table => {
+            buffer.extend_from_slice(b"</table>");
+        }
+        Node::TableRow => {
+            buffer.extend_from_slice(b"</tr>");
+        }
+        Node::TableCell {
+            header,
+            alignment: _,
+        } => {
+            buffer.extend_from_slice(if header { b"</th>" } else { b"</td>" });
+        }
+    }
+}
+
+// The checkbox ends up directly in the item or in its first paragraph, depending on the list.
+fn is_task(item: Ref<Node>) -> bool {
+    match item.child(0).map(|child| (child.value(), child)) {
+        Some((Node::Task { .. }, _)) => true,
+        Some((Node::Paragraph, paragraph)) => {
+            matches!(
+                paragraph.child(0).map(|c| c.value()),
+                Some(Node::Task { .. })
+            )
+        }
+        _ => false,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::markdown;
 
     #[test]
     fn test_begin_paragraph() {
@@ -246,6 +306,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_autolinks() {
+        let ast = markdown::parse(markdown::lex(
+            b"<https://a.b> www.a.b <a@b.c> [x](www.page.html) [y](user@host)",
+        ))
+        .ast;
+
+        assert_eq!(
+            String::from_utf8_lossy(&generate(ast)),
+            "<p><a href=\"https://a.b\">https://a.b</a> <a href=\"http://www.a.b\">www.a.b</a> \
+            <a href=\"mailto:a@b.c\">a@b.c</a> <a href=\"www.page.html\">x</a> \
+            <a href=\"user@host\">y</a></p>"
+        );
+    }
+
+    #[test]
+    fn test_task_list() {
+        let arena = Graph::new(Node::Empty);
+        let ol = arena.root().append_child(Node::List {
+            ordered: true,
+            indent: 0,
+        });
+        let li = ol.append_child(Node::ListItem);
+        li.append_child(Node::Task { checked: true });
+        li.append_child(Node::Text(b" done"));
+        let p = ol
+            .append_child(Node::ListItem)
+            .append_child(Node::Paragraph);
+        p.append_child(Node::Task { checked: false });
+        p.append_child(Node::Text(b" todo"));
+        ol.append_child(Node::ListItem)
+            .append_child(Node::Text(b"plain"));
+
+        assert_eq!(
+            String::from_utf8_lossy(&generate(arena)),
+            "<ol><li class=\"task-list-item\"><input type=\"checkbox\" disabled checked> done</li>\
+            <li class=\"task-list-item\"><p><input type=\"checkbox\" disabled> todo</p></li>\
+            <li>plain</li></ol>"
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        let arena = Graph::new(Node::Empty);
+        arena
+            .root()
+            .append_child(Node::Pre(b"rust"))
+            .append_child(Node::Text(b"fn main() {\n    x <  1;\n}"));
+        assert_eq!(
+            String::from_utf8_lossy(&html::minify(&generate(arena))),
+            "<pre><code class=\"language-rust\"><span class=\"keyword\">fn</span> main() {\n    \
+            x &lt;  <span class=\"number\">1</span>;\n}</code></pre>"
+        );
+    }
+
     #[test]
     fn test_escaping() {
         let arena = Graph::new(Node::Empty);
@@ -258,4 +373,37 @@ mod tests {
             "<p><code>&lt;tag&gt;</code></p>"
         );
     }
+
+    #[test]
+    fn test_tables() {
+        let arena = Graph::new(Node::Empty);
+        let table = arena.root().append_child(Node::Table);
+        let row = table.append_child(Node::TableRow);
+        row.append_child(Node::TableCell {
+            header: true,
+            alignment: Alignment::None,
+        })
+        .append_child(Node::Text(b"a"));
+        row.append_child(Node::TableCell {
+            header: true,
+            alignment: Alignment::Right,
+        })
+        .append_child(Node::Text(b"b"));
+        let row = table.append_child(Node::TableRow);
+        row.append_child(Node::TableCell {
+            header: false,
+            alignment: Alignment::None,
+        })
+        .append_child(Node::Emphasis(1))
+        .append_child(Node::Text(b"<c>"));
+        row.append_child(Node::TableCell {
+            header: false,
+            alignment: Alignment::Right,
+        });
+        assert_eq!(
+            String::from_utf8_lossy(&generate(arena)),
+            "<table><tr><th>a</th><th style=\"text-align:right\">b</th></tr>\
+            <tr><td><em>&lt;c&gt;</em></td><td style=\"text-align:right\"></td></tr></table>"
+        );
+    }
 }
@@ -0,0 +1,673 @@
+use super::escape;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Language {
+    Rust,
+    Python,
+    C,
+    JavaScript,
+    Shell,
+    Toml,
+    Html,
+}
+
+#[derive(Clone, Copy)]
+enum Class {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Type,
+    Literal,
+    Attribute,
+    Macro,
+    Variable,
+    Tag,
+}
+
+struct Syntax {
+    line_comment: &'static [u8],
+    block_comment: Option<(&'static [u8], &'static [u8])>,
+    // Space-separated words.
+    keywords: &'static str,
+    types: &'static str,
+    literals: &'static str,
+}
+
+struct Highlighter<'c> {
+    language: Language,
+    syntax: Syntax,
+    code: &'c [u8],
+    pos: usize,
+    plain_start: usize,
+    result: Vec<u8>,
+}
+
+const RUST: Syntax = Syntax {
+    line_comment: b"//",
+    block_comment: Some((b"/*", b"*/")),
+    keywords: "as async await break const continue crate dyn else enum extern fn for if impl in \
+        let loop match mod move mut pub ref return self Self static struct super trait type union \
+        unsafe use where while",
+    types: "bool char str u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64",
+    literals: "true false",
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comment: b"#",
+    block_comment: None,
+    keywords: "and as assert async await break class continue def del elif else except finally \
+        for from global if import in is lambda nonlocal not or pass raise return try while with \
+        yield",
+    types: "bool bytes dict float int list object set str tuple",
+    literals: "True False None",
+};
+
+const C: Syntax = Syntax {
+    line_comment: b"//",
+    block_comment: Some((b"/*", b"*/")),
+    keywords: "auto break case const continue default do else enum extern for goto if inline \
+        register restrict return sizeof static struct switch typedef union volatile while",
+    types: "bool char double float int long short signed unsigned void size_t ssize_t int8_t \
+        int16_t int32_t int64_t uint8_t uint16_t uint32_t uint64_t FILE",
+    literals: "NULL true false",
+};
+
+const JAVASCRIPT: Syntax = Syntax {
+    line_comment: b"//",
+    block_comment: Some((b"/*", b"*/")),
+    keywords: "async await break case catch class const continue debugger default delete do else \
+        export extends finally for function if import in instanceof let new of return static \
+        super switch this throw try typeof var void while with yield",
+    types: "",
+    literals: "true false null undefined NaN Infinity",
+};
+
+const SHELL: Syntax = Syntax {
+    line_comment: b"#",
+    block_comment: None,
+    keywords: "if then else elif fi for while until do done case esac function in select",
+    types: "",
+    literals: "",
+};
+
+const TOML: Syntax = Syntax {
+    line_comment: b"#",
+    block_comment: None,
+    keywords: "",
+    types: "",
+    literals: "true false inf nan",
+};
+
+const HTML: Syntax = Syntax {
+    line_comment: b"",
+    block_comment: Some((b"<!--", b"-->")),
+    keywords: "",
+    types: "",
+    literals: "",
+};
+
+fn is_word(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+fn utf8_len(first_byte: u8) -> usize {
+    match first_byte {
+        0xf0.. => 4,
+        0xe0.. => 3,
+        0xc0.. => 2,
+        _ => 1,
+    }
+}
+
+impl Language {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        Some(match name {
+            b"rust" | b"rs" => Self::Rust,
+            b"python" | b"py" => Self::Python,
+            b"c" | b"h" => Self::C,
+            b"javascript" | b"js" => Self::JavaScript,
+            b"sh" | b"bash" | b"shell" => Self::Shell,
+            b"toml" => Self::Toml,
+            b"html" => Self::Html,
+            _ => return None,
+        })
+    }
+
+    fn syntax(self) -> Syntax {
+        match self {
+            Self::Rust => RUST,
+            Self::Python => PYTHON,
+            Self::C => C,
+            Self::JavaScript => JAVASCRIPT,
+            Self::Shell => SHELL,
+            Self::Toml => TOML,
+            Self::Html => HTML,
+        }
+    }
+}
+
+impl Class {
+    fn name(self) -> &'static [u8] {
+        match self {
+            Self::Keyword => b"keyword",
+            Self::String => b"string",
+            Self::Number => b"number",
+            Self::Comment => b"comment",
+            Self::Type => b"type",
+            Self::Literal => b"literal",
+            Self::Attribute => b"attribute",
+            Self::Macro => b"macro",
+            Self::Variable => b"variable",
+            Self::Tag => b"tag",
+        }
+    }
+}
+
+impl Highlighter<'_> {
+    fn rest(&self) -> &[u8] {
+        &self.code[self.pos..]
+    }
+
+    fn char_at(&self, i: usize) -> u8 {
+        self.code.get(i).copied().unwrap_or(0)
+    }
+
+    // Shell words are only split by whitespace and operators, so `UTF-8` or `/24` are one word.
+    fn in_word(&self, c: u8) -> bool {
+        is_word(c)
+            || (self.language == Language::Shell && matches!(c, b'-' | b'.' | b'/' | b'=' | b':'))
+    }
+
+    fn at_word_start(&self) -> bool {
+        self.pos == 0 || !self.in_word(self.code[self.pos - 1])
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.code[..self.pos]
+            .iter()
+            .rev()
+            .take_while(|&&c| c != b'\n')
+            .all(|&c| c == b' ' || c == b'\t')
+    }
+
+    fn line_end(&self) -> usize {
+        self.find(b"\n", self.pos).unwrap_or(self.code.len())
+    }
+
+    fn word_end(&self, start: usize) -> usize {
+        start
+            + self.code[start..]
+                .iter()
+                .take_while(|&&c| is_word(c))
+                .count()
+    }
+
+    fn find(&self, needle: &[u8], start: usize) -> Option<usize> {
+        self.code
+            .get(start..)?
+            .windows(needle.len())
+            .position(|window| window == needle)
+            .map(|i| start + i)
+    }
+
+    // Returns the end of the string whose contents begin at `start`, past its closing quote.
+    fn quoted_end(&self, start: usize, quote: &[u8], escapes: bool, multiline: bool) -> usize {
+        let mut i = start;
+        while i < self.code.len() {
+            if self.code[i..].starts_with(quote) {
+                return i + quote.len();
+            }
+            match self.code[i] {
+                b'\\' if escapes => i += 1,
+                b'\n' if !multiline => return i,
+                _ => {}
+            }
+            i += 1;
+        }
+        self.code.len()
+    }
+
+    fn char_literal_end(&self, start: usize) -> Option<usize> {
+        let end = if self.char_at(start) == b'\\' {
+            self.quoted_end(start + 2, b"'", false, false)
+        } else {
+            start + utf8_len(self.char_at(start)) + 1
+        };
+        (self.char_at(end - 1) == b'\'' && end - start <= 12).then_some(end)
+    }
+
+    fn string_end(&self) -> Option<usize> {
+        let i = self.pos;
+        match self.language {
+            Language::Rust => {
+                let prefix = if self.at_word_start() {
+                    self.rest()
+                        .iter()
+                        .take(2)
+                        .take_while(|&&c| matches!(c, b'b' | b'c' | b'r'))
+                        .count()
+                } else {
+                    0
+                };
+                let start = i + prefix;
+                let raw = self.code[i..start].contains(&b'r');
+                let hashes = if raw {
+                    self.code[start..]
+                        .iter()
+                        .take_while(|&&c| c == b'#')
+                        .count()
+                } else {
+                    0
+                };
+                match self.char_at(start + hashes) {
+                    b'"' if raw => {
+                        let mut closing = b"\"".to_vec();
+                        closing.resize(1 + hashes, b'#');
+                        Some(self.quoted_end(start + hashes + 1, &closing, false, true))
+                    }
+                    b'"' => Some(self.quoted_end(start + 1, b"\"", true, true)),
+                    b'\'' if prefix == 0 || &self.code[i..start] == b"b" => {
+                        self.char_literal_end(start + 1)
+                    }
+                    _ => None,
+                }
+            }
+            Language::Python => {
+                let prefix = if self.at_word_start() {
+                    self.rest()
+                        .iter()
+                        .take(2)
+                        .take_while(|&&c| b"rRbBfFuU".contains(&c))
+                        .count()
+                } else {
+                    0
+                };
+                let start = i + prefix;
+                let quote = match self.char_at(start) {
+                    q @ (b'"' | b'\'') => q,
+                    _ => return None,
+                };
+                if self.code[start..].starts_with(&[quote; 3]) {
+                    Some(self.quoted_end(start + 3, &[quote; 3], true, true))
+                } else {
+                    Some(self.quoted_end(start + 1, &[quote], true, false))
+                }
+            }
+            Language::C | Language::JavaScript | Language::Shell => match self.char_at(i) {
+                b'`' if self.language == Language::JavaScript => {
+                    Some(self.quoted_end(i + 1, b"`", true, true))
+                }
+                b'\'' if self.language == Language::Shell => {
+                    Some(self.quoted_end(i + 1, b"'", false, false))
+                }
+                q @ (b'"' | b'\'') => Some(self.quoted_end(i + 1, &[q], true, false)),
+                _ => None,
+            },
+            Language::Toml => {
+                let quote = match self.char_at(i) {
+                    q @ (b'"' | b'\'') => q,
+                    _ => return None,
+                };
+                let escapes = quote == b'"';
+                if self.rest().starts_with(&[quote; 3]) {
+                    Some(self.quoted_end(i + 3, &[quote; 3], escapes, true))
+                } else {
+                    Some(self.quoted_end(i + 1, &[quote], escapes, false))
+                }
+            }
+            Language::Html => None,
+        }
+    }
+
+    // Constructs that only make sense in one language, such as attributes or variables.
+    fn special(&self) -> Option<(Class, usize)> {
+        let i = self.pos;
+        let c = self.char_at(i);
+        match self.language {
+            Language::Rust if c == b'#' && matches!(self.char_at(i + 1), b'[' | b'!') => {
+                let mut depth = 0;
+                for (j, &d) in self.code[i..].iter().enumerate() {
+                    match d {
+                        b'[' => depth += 1,
+                        b']' if depth == 1 => return Some((Class::Attribute, i + j + 1)),
+                        b']' => depth -= 1,
+                        b'\n' => break,
+                        _ => {}
+                    }
+                }
+                None
+            }
+            Language::Rust if c == b'\'' && self.char_literal_end(i + 1).is_none() => {
+                // Lifetimes and labels, which are not strings but should not be keywords either.
+                let end = self.word_end(i + 1);
+                (end > i + 1).then_some((Class::Type, end))
+            }
+            Language::Python if c == b'@' && self.at_line_start() => {
+                let end = i
+                    + 1
+                    + self.code[i + 1..]
+                        .iter()
+                        .take_while(|&&c| is_word(c) || c == b'.')
+                        .count();
+                Some((Class::Attribute, end))
+            }
+            Language::C if c == b'#' && self.at_line_start() => {
+                Some((Class::Attribute, self.line_end()))
+            }
+            Language::Shell if c == b'$' => match self.char_at(i + 1) {
+                b'{' => Some((
+                    Class::Variable,
+                    self.find(b"}", i + 2).map_or(self.line_end(), |j| j + 1),
+                )),
+                b'?' | b'#' | b'@' | b'*' | b'$' | b'!' | b'-' | b'0'..=b'9' => {
+                    Some((Class::Variable, i + 2))
+                }
+                d if is_word(d) => Some((Class::Variable, self.word_end(i + 1))),
+                _ => None,
+            },
+            Language::Toml if self.at_line_start() => {
+                if c == b'[' {
+                    let end = self.line_end();
+                    let j = self.code[i..end].iter().rposition(|&c| c == b']')?;
+                    return Some((Class::Type, i + j + 1));
+                }
+                let end = match c {
+                    b'"' | b'\'' => self.quoted_end(i + 1, &[c], c == b'"', false),
+                    _ => {
+                        i + self.code[i..]
+                            .iter()
+                            .take_while(|&&c| is_word(c) || c == b'-' || c == b'.')
+                            .count()
+                    }
+                };
+                let after = self.code[end..].trim_ascii_start();
+                (end > i && after.starts_with(b"=")).then_some((Class::Attribute, end))
+            }
+            _ => None,
+        }
+    }
+
+    fn word_class(&self, end: usize) -> Option<(Class, usize)> {
+        let word = &self.code[self.pos..end];
+        let is_in = |words: &str| words.split(' ').any(|w| w.as_bytes() == word);
+        if is_in(self.syntax.keywords) {
+            Some((Class::Keyword, end))
+        } else if is_in(self.syntax.literals) {
+            Some((Class::Literal, end))
+        } else if is_in(self.syntax.types) {
+            Some((Class::Type, end))
+        } else if self.language == Language::Rust
+            && self.char_at(end) == b'!'
+            && self.char_at(end + 1) != b'='
+        {
+            Some((Class::Macro, end + 1))
+        } else if self.language == Language::Rust
+            && word[0].is_ascii_uppercase()
+            && word.iter().any(u8::is_ascii_lowercase)
+        {
+            // By convention, these name types (and their constructors) rather than constants.
+            Some((Class::Type, end))
+        } else {
+            None
+        }
+    }
+
+    fn flush_plain(&mut self) {
+        self.result
+            .extend_from_slice(&escape(&self.code[self.plain_start..self.pos]));
+        self.plain_start = self.pos;
+    }
+
+    fn span(&mut self, class: Class, end: usize) {
+        self.flush_plain();
+        self.result.extend_from_slice(b"<span class=\"");
+        self.result.extend_from_slice(class.name());
+        self.result.extend_from_slice(b"\">");
+        self.result
+            .extend_from_slice(&escape(&self.code[self.pos..end]));
+        self.result.extend_from_slice(b"</span>");
+        self.pos = end;
+        self.plain_start = end;
+    }
+
+    fn run(mut self) -> Vec<u8> {
+        if self.language == Language::Html {
+            return self.run_html();
+        }
+
+        while self.pos < self.code.len() {
+            let i = self.pos;
+            let c = self.code[i];
+            let rest = self.rest();
+
+            if let Some((start, end)) = self.syntax.block_comment
+                && rest.starts_with(start)
+            {
+                let end = self
+                    .find(end, i + start.len())
+                    .map_or(self.code.len(), |j| j + end.len());
+                self.span(Class::Comment, end);
+            } else if !self.syntax.line_comment.is_empty()
+                && rest.starts_with(self.syntax.line_comment)
+                && (self.language != Language::Shell
+                    || i == 0
+                    || self.code[i - 1].is_ascii_whitespace())
+            {
+                self.span(Class::Comment, self.line_end());
+            } else if let Some((class, end)) = self.special() {
+                self.span(class, end);
+            } else if let Some(end) = self.string_end() {
+                self.span(Class::String, end);
+            } else if c.is_ascii_digit() && self.at_word_start() {
+                let mut end = i;
+                while is_word(self.char_at(end))
+                    || (self.char_at(end) == b'.' && self.char_at(end + 1).is_ascii_digit())
+                {
+                    end += 1;
+                }
+                if self.in_word(self.char_at(end)) {
+                    self.pos = end;
+                } else {
+                    self.span(Class::Number, end);
+                }
+            } else if is_word(c) {
+                let end = self.word_end(i);
+                match self.word_class(end).filter(|_| self.at_word_start()) {
+                    Some((class, end)) => self.span(class, end),
+                    None => self.pos = end,
+                }
+            } else {
+                self.pos += 1;
+            }
+        }
+
+        self.flush_plain();
+        self.result
+    }
+
+    fn run_html(mut self) -> Vec<u8> {
+        while self.pos < self.code.len() {
+            let i = self.pos;
+            let rest = self.rest();
+
+            if rest.starts_with(b"<!--") {
+                let end = self.find(b"-->", i + 4).map_or(self.code.len(), |j| j + 3);
+                self.span(Class::Comment, end);
+            } else if rest[0] == b'<'
+                && (self.char_at(i + 1).is_ascii_alphabetic()
+                    || (matches!(self.char_at(i + 1), b'/' | b'!')
+                        && self.char_at(i + 2).is_ascii_alphabetic()))
+            {
+                let name_start = if self.char_at(i + 1).is_ascii_alphabetic() {
+                    i + 1
+                } else {
+                    i + 2
+                };
+                let name_end = name_start
+                    + self.code[name_start..]
+                        .iter()
+                        .take_while(|&&c| c.is_ascii_alphanumeric() || c == b'-')
+                        .count();
+                self.span(Class::Tag, name_end);
+                self.html_attributes();
+            } else if rest[0] == b'&' {
+                let end = i
+                    + 1
+                    + rest[1..]
+                        .iter()
+                        .take_while(|&&c| c.is_ascii_alphanumeric() || c == b'#')
+                        .count();
+                if end > i + 1 && self.char_at(end) == b';' {
+                    self.span(Class::Literal, end + 1);
+                } else {
+                    self.pos += 1;
+                }
+            } else {
+                self.pos += 1;
+            }
+        }
+
+        self.flush_plain();
+        self.result
+    }
+
+    fn html_attributes(&mut self) {
+        let mut after_equals = false;
+        while self.pos < self.code.len() {
+            let i = self.pos;
+            match self.code[i] {
+                b'>' => return self.span(Class::Tag, i + 1),
+                b'/' if self.char_at(i + 1) == b'>' => return self.span(Class::Tag, i + 2),
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                b'=' => {
+                    after_equals = true;
+                    self.pos += 1;
+                }
+                q @ (b'"' | b'\'') => {
+                    after_equals = false;
+                    self.span(Class::String, self.quoted_end(i + 1, &[q], false, true));
+                }
+                _ => {
+                    let end = i + self.code[i..]
+                        .iter()
+                        .take_while(|&&c| {
+                            !c.is_ascii_whitespace()
+                                && c != b'>'
+                                && (after_equals || !matches!(c, b'=' | b'/'))
+                        })
+                        .count();
+                    let class = if after_equals {
+                        Class::String
+                    } else {
+                        Class::Attribute
+                    };
+                    after_equals = false;
+                    self.span(class, end.max(i + 1));
+                }
+            }
+        }
+    }
+}
+
+// Unknown languages are only escaped. Otherwise, tokens are wrapped in spans with a class.
+pub fn highlight(lang: &[u8], code: &[u8]) -> Vec<u8> {
+    match Language::from_name(lang) {
+        Some(language) => Highlighter {
+            language,
+            syntax: language.syntax(),
+            code,
+            pos: 0,
+            plain_start: 0,
+            result: Vec::with_capacity(code.len() * 2),
+        }
+        .run(),
+        None => escape(code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::text_content;
+
+    fn spans(lang: &str, code: &str) -> String {
+        String::from_utf8(highlight(lang.as_bytes(), code.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            spans("rust", "let c = 'x'; // <c>"),
+            "<span class=\"keyword\">let</span> c = <span class=\"string\">'x'</span>; \
+            <span class=\"comment\">// &lt;c&gt;</span>"
+        );
+        assert_eq!(
+            spans("rust", "#[test] fn f<'a>() { vec![1.5]; }"),
+            "<span class=\"attribute\">#[test]</span> <span class=\"keyword\">fn</span> \
+            f&lt;<span class=\"type\">'a</span>&gt;() { <span class=\"macro\">vec!</span>\
+            [<span class=\"number\">1.5</span>]; }"
+        );
+        assert_eq!(
+            spans("python", "x = rb'\\'' # None"),
+            "x = <span class=\"string\">rb'\\''</span> <span class=\"comment\"># None</span>"
+        );
+        assert_eq!(
+            spans("sh", "echo $HOME a#b"),
+            "echo <span class=\"variable\">$HOME</span> a#b"
+        );
+        assert_eq!(
+            spans("toml", "[a]\nb = true"),
+            "<span class=\"type\">[a]</span>\n<span class=\"attribute\">b</span> = \
+            <span class=\"literal\">true</span>"
+        );
+        assert_eq!(
+            spans("html", "<a href=\"x\">&amp;</a>"),
+            "<span class=\"tag\">&lt;a</span> <span class=\"attribute\">href</span>=\
+            <span class=\"string\">\"x\"</span><span class=\"tag\">&gt;</span>\
+            <span class=\"literal\">&amp;amp;</span><span class=\"tag\">&lt;/a</span>\
+            <span class=\"tag\">&gt;</span>"
+        );
+        assert_eq!(
+            spans("sh", "ntfs-3g UTF-8 10.0.0.1/24 ::1 x=if 2"),
+            "ntfs-3g UTF-8 10.0.0.1/24 ::1 x=if <span class=\"number\">2</span>"
+        );
+        assert_eq!(
+            spans("python", "class Point: SetCursorPos(1)"),
+            "<span class=\"keyword\">class</span> Point: SetCursorPos(<span class=\"number\">1</span>)"
+        );
+        assert_eq!(spans("asm", "mov <eax>, 1"), "mov &lt;eax&gt;, 1");
+    }
+
+    #[test]
+    fn test_text_is_preserved() {
+        let samples = [
+            (
+                "rust",
+                "let r = r#\"raw \"str\"\"#; b'\\''; 'label: loop { x != y }",
+            ),
+            (
+                "python",
+                "@deco\ndef f():\n    \"\"\"doc\n    string\"\"\"\n    return f'{x}'",
+            ),
+            (
+                "c",
+                "#include <stdio.h>\nint main(void) { /* a\n */ return 0x1f; }",
+            ),
+            ("js", "const s = `tpl ${a}`; // 'unclosed"),
+            (
+                "sh",
+                "echo \"$1\" ${PATH} 'it # s' # comment\nif [ -f x ]; then :; fi",
+            ),
+            ("toml", "\"key\" = '''\nmulti'''\ntags = [\n  \"a\",\n]"),
+            ("html", "<!-- c --><br/><p class=x>unclosed \"quote"),
+            ("rust", "unterminated \"string"),
+        ];
+        for (lang, code) in samples {
+            assert_eq!(
+                text_content(&highlight(lang.as_bytes(), code.as_bytes())),
+                escape(code.as_bytes())
+            );
+        }
+    }
+}
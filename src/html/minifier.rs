@@ -25,13 +25,13 @@ pub fn minify(html: &[u8]) -> Vec<u8> {
             in_other = Some(b"</script>");
             minified.push(c);
         } else if in_comment {
-            if matches!(html.get(i - 2..i + 1), Some(b"-->")) {
+            if matches!(html.get(i.saturating_sub(2)..i + 1), Some(b"-->")) {
                 in_comment = false
             }
         } else if matches!(html.get(i..i + 4), Some(b"<!--")) {
             in_comment = true
         } else if in_pre {
-            if matches!(html.get(i - 5..i + 1), Some(b"</pre>")) {
+            if matches!(html.get(i.saturating_sub(5)..i + 1), Some(b"</pre>")) {
                 in_pre = false
             }
             minified.push(c);
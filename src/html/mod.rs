@@ -1,4 +1,5 @@
 mod generator;
+mod highlight;
 mod minifier;
 
 use std::array;
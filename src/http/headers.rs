@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct Headers {
+    map: HashMap<String, String>,
+}
+
+pub fn is_valid_name(name: &[u8]) -> bool {
+    !name.is_empty()
+        && name.iter().all(|&c| {
+            c.is_ascii_alphanumeric()
+                || matches!(
+                    c,
+                    b'!' | b'#'
+                        | b'$'
+                        | b'%'
+                        | b'&'
+                        | b'\''
+                        | b'*'
+                        | b'+'
+                        | b'-'
+                        | b'.'
+                        | b'^'
+                        | b'_'
+                        | b'`'
+                        | b'|'
+                        | b'~'
+                )
+        })
+}
+
+impl Headers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.map
+            .get(&name.to_ascii_lowercase())
+            .map(|value| value.as_str())
+    }
+
+    pub fn insert(&mut self, name: &str, value: &str) {
+        // Repeated fields are equivalent to a single comma-separated one.
+        self.map
+            .entry(name.to_ascii_lowercase())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_owned());
+    }
+
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        self.get(name).is_some_and(|value| {
+            value
+                .split(',')
+                .any(|t| t.trim().eq_ignore_ascii_case(token))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_insensitive() {
+        let mut headers = Headers::new();
+        headers.insert("Content-Length", "0");
+        headers.insert("connection", "keep-alive");
+        headers.insert("CONNECTION", "Upgrade");
+
+        assert_eq!(headers.get("content-length"), Some("0"));
+        assert_eq!(headers.get("Connection"), Some("keep-alive, Upgrade"));
+        assert!(headers.has_token("connection", "upgrade"));
+        assert!(!headers.has_token("connection", "close"));
+        assert_eq!(headers.get("Host"), None);
+    }
+
+    #[test]
+    fn test_valid_name() {
+        assert!(is_valid_name(b"If-None-Match"));
+        assert!(!is_valid_name(b""));
+        assert!(!is_valid_name(b"Bad Name"));
+        assert!(!is_valid_name(b"Bad:Name"));
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write as _;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::{entry, html};
+
+pub const ENDPOINT: &str = "/_site/live-reload";
+
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const SNIPPET: &[u8] = br#"<script>(() => {
+  const events = new EventSource("/_site/live-reload")
+  events.addEventListener("reload", () => location.reload())
+  events.addEventListener("style", () => {
+    for (const link of document.querySelectorAll('link[rel="stylesheet"]')) {
+      const url = new URL(link.href)
+      url.searchParams.set("live-reload", Date.now())
+      link.href = url
+    }
+  })
+})()</script>"#;
+
+const OVERLAY_STYLE: &str = "position:fixed;inset:0;z-index:2147483647;overflow:auto;margin:0;\
+    padding:2em;background:#1e1e1ef2;color:#eee;font:14px/1.5 monospace;white-space:pre-wrap";
+
+// A broken template fails every page, which is better summarized than listed.
+const OVERLAY_LIMIT: usize = 5;
+
+#[derive(Clone, Copy)]
+pub enum Change {
+    Page,
+    Style,
+}
+
+pub struct LiveReload {
+    clients: Mutex<Vec<TcpStream>>,
+    failures: Mutex<HashMap<PathBuf, Vec<u8>>>,
+}
+
+impl Change {
+    fn event(&self) -> &'static [u8] {
+        match self {
+            Self::Page => b"event: reload\ndata:\n\n",
+            Self::Style => b"event: style\ndata:\n\n",
+        }
+    }
+}
+
+impl LiveReload {
+    pub fn new() -> Self {
+        Self {
+            clients: Mutex::new(Vec::new()),
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn subscribe(&self, mut stream: TcpStream) {
+        if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err()
+            || stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\n\
+                    Content-Type: text/event-stream\r\n\
+                    Cache-Control: no-cache\r\n\r\n",
+                )
+                .is_err()
+        {
+            return;
+        }
+        self.clients.lock().unwrap().push(stream);
+    }
+
+    pub fn notify(&self, change: Change) {
+        // Clients that went away are only noticed (and dropped) once writing to them fails.
+        self.clients
+            .lock()
+            .unwrap()
+            .retain_mut(|stream| stream.write_all(change.event()).is_ok());
+    }
+
+    // Shows the error on top of every page until `recover` is called with the same path.
+    pub fn fail(&self, path: &Path, error: &entry::Error) {
+        let source = fs::read(path).ok();
+        let line = source
+            .as_deref()
+            .filter(|_| error.line != 0)
+            .and_then(|source| source.split(|&c| c == b'\n').nth(error.line - 1));
+        self.failures
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), failure(path, error, line));
+    }
+
+    // Clears the errors of the path, or of everything inside it if it's a directory.
+    // Returns whether there were any.
+    pub fn recover(&self, path: &Path) -> bool {
+        let mut failures = self.failures.lock().unwrap();
+        let count = failures.len();
+        failures.retain(|failed, _| !failed.starts_with(path));
+        failures.len() != count
+    }
+
+    pub fn inject(&self, html: &mut Vec<u8>) {
+        if let Some(overlay) = overlay(&self.failures.lock().unwrap()) {
+            insert_before_body_end(html, &overlay);
+        }
+        insert_before_body_end(html, SNIPPET);
+    }
+}
+
+impl Default for LiveReload {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn insert_before_body_end(html: &mut Vec<u8>, snippet: &[u8]) {
+    const BODY_END: &[u8] = b"</body>";
+    let i = html
+        .windows(BODY_END.len())
+        .rposition(|window| window == BODY_END)
+        .unwrap_or(html.len());
+    html.splice(i..i, snippet.iter().copied());
+}
+
+fn overlay(failures: &HashMap<PathBuf, Vec<u8>>) -> Option<Vec<u8>> {
+    if failures.is_empty() {
+        return None;
+    }
+
+    let mut paths = failures.keys().collect::<Vec<_>>();
+    paths.sort();
+    let mut result = format!("<pre style=\"{OVERLAY_STYLE}\"><b>build failed</b>").into_bytes();
+    for path in paths.iter().take(OVERLAY_LIMIT) {
+        result.extend(b"\n\n");
+        result.extend(&failures[*path]);
+    }
+    if paths.len() > OVERLAY_LIMIT {
+        result.extend(format!("\n\n…and {} more files", paths.len() - OVERLAY_LIMIT).into_bytes());
+    }
+    result.extend(b"</pre>");
+    Some(result)
+}
+
+fn failure(path: &Path, error: &entry::Error, line: Option<&[u8]>) -> Vec<u8> {
+    let mut result = html::escape(path.to_string_lossy().as_bytes());
+    if error.line != 0 {
+        result.extend(format!(":{}", error.line).into_bytes());
+    }
+    result.extend(b"\n");
+    result.extend(html::escape(error.message.as_bytes()));
+    if let Some(line) = line {
+        result.extend(format!("\n\n{} | ", error.line).into_bytes());
+        result.extend(html::escape(line.trim_ascii_end()));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inject() {
+        let live_reload = LiveReload::new();
+        let mut html = b"<html><body><p>text</p></body></html>".to_vec();
+        live_reload.inject(&mut html);
+        assert!(html.starts_with(b"<html><body><p>text</p><script>"));
+        assert!(html.ends_with(b"</script></body></html>"));
+
+        let mut html = b"<p>fragment</p>".to_vec();
+        live_reload.inject(&mut html);
+        assert!(html.starts_with(b"<p>fragment</p><script>"));
+    }
+
+    #[test]
+    fn test_overlay() {
+        let live_reload = LiveReload::new();
+        let error = entry::Error {
+            line: 2,
+            message: String::from("metadata is not valid utf8"),
+        };
+        let failure = failure(Path::new("content/<post>.md"), &error, Some(b"title = x\r"));
+        assert_eq!(
+            String::from_utf8(failure).unwrap(),
+            "content/&lt;post&gt;.md:2\nmetadata is not valid utf8\n\n2 | title = x"
+        );
+
+        let (a, b) = (Path::new("content/a.md"), Path::new("content/b.md"));
+        live_reload.fail(a, &error);
+        live_reload.fail(b, &error);
+        let mut html = b"<body></body>".to_vec();
+        live_reload.inject(&mut html);
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.starts_with("<body><pre "));
+        assert!(html.contains("content/a.md:2\nmetadata is not valid utf8\n\ncontent/b.md:2"));
+        assert!(!html.contains("2 | "));
+
+        // Other files being fine does not fix the broken ones.
+        assert!(!live_reload.recover(Path::new("content/c.md")));
+        assert!(live_reload.recover(a));
+        assert!(!live_reload.recover(a));
+        let mut html = b"<body></body>".to_vec();
+        live_reload.inject(&mut html);
+        assert!(!String::from_utf8(html).unwrap().contains("a.md"));
+
+        assert!(live_reload.recover(Path::new("content")));
+        let mut html = b"<body></body>".to_vec();
+        live_reload.inject(&mut html);
+        assert!(html.starts_with(b"<body><script>"));
+
+        for i in 0..OVERLAY_LIMIT + 2 {
+            live_reload.fail(&PathBuf::from(format!("{i}.md")), &error);
+        }
+        let mut html = b"<body></body>".to_vec();
+        live_reload.inject(&mut html);
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("4.md") && !html.contains("5.md"));
+        assert!(html.contains("…and 2 more files</pre>"));
+    }
+}
@@ -1,12 +1,21 @@
+mod headers;
+pub mod live_reload;
+mod pool;
 mod request;
 mod response;
+pub mod rules;
 pub mod server;
+pub mod shutdown;
 mod status;
+mod target;
 
-pub use request::{ParsedRequest, Request};
+pub use headers::Headers;
+pub use live_reload::LiveReload;
+pub use request::{Method, ParsedRequest, Ranges, Request, Version};
 pub use response::Response;
+pub use server::{Server, StopHandle};
 pub use status::Status;
+pub use target::{Query, Target, encode_path};
 
-pub const VERSION: &[u8; 8] = b"HTTP/1.1";
 pub const EOL: &[u8; 2] = b"\r\n";
 pub const EOH: &[u8; 4] = b"\r\n\r\n";
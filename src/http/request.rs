@@ -1,12 +1,42 @@
-use super::{EOH, EOL, Status, VERSION};
+use std::ops::Range;
+
+use super::{EOH, EOL, Headers, Query, Status, Target, headers};
+
+pub const REQUEST_HEADERS_MAX_SIZE: usize = 1024;
+pub const REQUEST_BODY_MAX_SIZE: usize = 1024;
+const MAX_RANGES: usize = 16;
 
 static REQUEST_LINE_GET_SPACE: &[u8] = b"GET ";
+static REQUEST_LINE_HEAD_SPACE: &[u8] = b"HEAD ";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Method {
+    Get,
+    Head,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Version {
+    Http10,
+    Http11,
+}
 
 pub struct Request {
+    pub method: Method,
+    pub version: Version,
     pub target: String,
+    pub path: String,
+    pub query: Query,
+    pub headers: Headers,
     pub body: Vec<u8>,
 }
 
+pub enum Ranges {
+    Absent,
+    Satisfiable(Vec<Range<usize>>),
+    Unsatisfiable,
+}
+
 pub enum ParsedRequest {
     Ok { request: Request, consumed: usize },
     Err(Status),
@@ -20,50 +50,355 @@ fn split_request_line(buffer: &[u8]) -> (&[u8], &[u8]) {
     }
 }
 
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+        }
+    }
+}
+
+impl Version {
+    pub fn as_bytes(&self) -> &'static [u8; 8] {
+        match self {
+            Self::Http10 => b"HTTP/1.0",
+            Self::Http11 => b"HTTP/1.1",
+        }
+    }
+}
+
 impl Request {
-    pub fn from_buffer(mut buffer: &[u8]) -> ParsedRequest {
-        let eoh = match buffer.windows(EOH.len()).position(|window| window == EOH) {
+    // HTTP/1.0 connections only persist when the client explicitly asks for it.
+    pub fn keep_alive(&self) -> bool {
+        match self.version {
+            Version::Http10 => self.headers.has_token("Connection", "keep-alive"),
+            Version::Http11 => !self.headers.has_token("Connection", "close"),
+        }
+    }
+
+    // An encoding is acceptable if listed, or covered by `*`, with a non-zero quality.
+    pub fn accepts_encoding(&self, encoding: &str) -> bool {
+        let value = match self.headers.get("Accept-Encoding") {
+            Some(value) => value,
+            None => return false,
+        };
+
+        let mut wildcard = false;
+        for item in value.split(',') {
+            let mut parameters = item.split(';');
+            let coding = parameters.next().unwrap_or_default().trim();
+            let accepted = parameters
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .all(|q| q.trim().parse::<f32>().is_ok_and(|q| q > 0.0));
+            if coding.eq_ignore_ascii_case(encoding) {
+                return accepted;
+            } else if coding == "*" {
+                wildcard = accepted;
+            }
+        }
+        wildcard
+    }
+
+    pub fn ranges(&self, length: usize) -> Ranges {
+        let specs = match self
+            .headers
+            .get("Range")
+            .and_then(|r| r.strip_prefix("bytes="))
+        {
+            Some(specs) => specs,
+            None => return Ranges::Absent,
+        };
+
+        let mut ranges = Vec::new();
+        for spec in specs.split(',').map(|spec| spec.trim()) {
+            let (first, last) = match spec.split_once('-') {
+                Some(x) => x,
+                None => return Ranges::Unsatisfiable,
+            };
+            let range = match (first.parse::<usize>(), last.parse::<usize>()) {
+                (Ok(first), Ok(last)) if first <= last => first..length.min(last + 1),
+                (Ok(first), Err(_)) if last.is_empty() => first..length,
+                (Err(_), Ok(suffix)) if first.is_empty() => length.saturating_sub(suffix)..length,
+                _ => return Ranges::Unsatisfiable,
+            };
+            // Ranges past the end are skipped, but the others may still be satisfied.
+            if !range.is_empty() {
+                ranges.push(range);
+            }
+        }
+
+        if ranges.is_empty() {
+            Ranges::Unsatisfiable
+        } else if ranges.len() > MAX_RANGES {
+            // Too many ranges are not worth the effort, so serve the whole thing instead.
+            Ranges::Absent
+        } else {
+            Ranges::Satisfiable(ranges)
+        }
+    }
+
+    pub fn from_buffer(full_buffer: &[u8]) -> ParsedRequest {
+        let eoh = match full_buffer
+            .windows(EOH.len())
+            .position(|window| window == EOH)
+        {
+            Some(i) if i + EOH.len() > REQUEST_HEADERS_MAX_SIZE => {
+                return ParsedRequest::Err(Status::RequestHeaderFieldsTooLarge);
+            }
             Some(i) => i + EOH.len(),
+            None if full_buffer.len() >= REQUEST_HEADERS_MAX_SIZE => {
+                return ParsedRequest::Err(Status::RequestHeaderFieldsTooLarge);
+            }
             None => return ParsedRequest::TooShort,
         };
-        buffer = &buffer[..eoh];
+        let mut buffer = &full_buffer[..eoh];
 
         let request_line;
         (request_line, buffer) = split_request_line(buffer);
 
-        if !request_line.ends_with(VERSION)
-            || request_line
-                .get(request_line.len().saturating_sub(VERSION.len() + 1))
-                .is_none_or(|&c| c != b' ')
+        let version_len = Version::Http11.as_bytes().len();
+        let version = match request_line
+            .len()
+            .checked_sub(version_len + 1)
+            .map(|i| (request_line[i], &request_line[i + 1..]))
         {
-            return ParsedRequest::Err(Status::HttpVersionNotSupported);
-        }
-        if !request_line.starts_with(REQUEST_LINE_GET_SPACE) {
+            Some((b' ', b"HTTP/1.1")) => Version::Http11,
+            Some((b' ', b"HTTP/1.0")) => Version::Http10,
+            _ => return ParsedRequest::Err(Status::HttpVersionNotSupported),
+        };
+        let (method, method_space_len) = if request_line.starts_with(REQUEST_LINE_GET_SPACE) {
+            (Method::Get, REQUEST_LINE_GET_SPACE.len())
+        } else if request_line.starts_with(REQUEST_LINE_HEAD_SPACE) {
+            (Method::Head, REQUEST_LINE_HEAD_SPACE.len())
+        } else {
             return ParsedRequest::Err(Status::MethodNotAllowed);
-        }
+        };
 
         let request_target = &request_line
-            [REQUEST_LINE_GET_SPACE.len()..request_line.len() - VERSION.len() - 1]
+            .get(method_space_len..request_line.len() - version_len - 1)
+            .unwrap_or_default()
             .trim_ascii_end();
 
-        if request_target.is_empty() {
-            return ParsedRequest::Err(Status::BadRequest);
-        }
+        let Target { path, query } = match Target::parse(request_target) {
+            Ok(target) => target,
+            Err(status) => return ParsedRequest::Err(status),
+        };
 
-        let request_body = loop {
+        let mut headers = Headers::new();
+        loop {
             let header;
             (header, buffer) = split_request_line(buffer);
             if header.is_empty() {
-                break buffer;
+                break;
             }
+            // Whitespace before the colon and obsolete line folding are both rejected.
+            let i = match header.iter().position(|&c| c == b':') {
+                Some(i) if headers::is_valid_name(&header[..i]) => i,
+                _ => return ParsedRequest::Err(Status::BadRequest),
+            };
+            headers.insert(
+                &String::from_utf8_lossy(&header[..i]),
+                &String::from_utf8_lossy(header[i + 1..].trim_ascii()),
+            );
+        }
+
+        if headers.get("Transfer-Encoding").is_some() {
+            return ParsedRequest::Err(Status::NotImplemented);
+        }
+        let content_length = match headers.get("Content-Length").map(|v| v.parse::<usize>()) {
+            None => 0,
+            Some(Ok(length)) if length > REQUEST_BODY_MAX_SIZE => {
+                return ParsedRequest::Err(Status::ContentTooLarge);
+            }
+            Some(Ok(length)) => length,
+            // Also covers differing values, which were joined with commas.
+            Some(Err(_)) => return ParsedRequest::Err(Status::BadRequest),
+        };
+        let body = match full_buffer.get(eoh..eoh + content_length) {
+            Some(body) => body,
+            None => return ParsedRequest::TooShort,
         };
 
         ParsedRequest::Ok {
             request: Request {
+                method,
+                version,
                 target: String::from_utf8_lossy(request_target).into_owned(),
-                body: request_body.to_vec(),
+                path,
+                query,
+                headers,
+                body: body.to_vec(),
             },
-            consumed: eoh,
+            consumed: eoh + content_length,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_ok(buffer: &[u8]) -> Request {
+        match Request::from_buffer(buffer) {
+            ParsedRequest::Ok { request, .. } => request,
+            _ => panic!("request to be parsed"),
         }
     }
+
+    #[test]
+    fn test_methods() {
+        let request = parse_ok(b"GET / HTTP/1.1\r\n\r\n");
+        assert!(request.method == Method::Get);
+
+        let request = parse_ok(b"HEAD /style.css HTTP/1.1\r\n\r\n");
+        assert!(request.method == Method::Head);
+        assert_eq!(request.target, "/style.css");
+
+        assert!(matches!(
+            Request::from_buffer(b"POST / HTTP/1.1\r\n\r\n"),
+            ParsedRequest::Err(Status::MethodNotAllowed)
+        ));
+        assert!(matches!(
+            Request::from_buffer(b"GET / HTTP/1.1\r\n"),
+            ParsedRequest::TooShort
+        ));
+    }
+
+    #[test]
+    fn test_accepts_encoding() {
+        let accepts = |value: &str| {
+            parse_ok(format!("GET / HTTP/1.1\r\nAccept-Encoding: {value}\r\n\r\n").as_bytes())
+                .accepts_encoding("gzip")
+        };
+        assert!(!parse_ok(b"GET / HTTP/1.1\r\n\r\n").accepts_encoding("gzip"));
+        assert!(accepts("gzip, deflate, br"));
+        assert!(accepts("br;q=1.0, GZIP;q=0.5"));
+        assert!(accepts("*"));
+        assert!(!accepts("br"));
+        assert!(!accepts("gzip;q=0"));
+        assert!(!accepts("*, gzip;q=0"));
+        assert!(!accepts("identity"));
+    }
+
+    #[test]
+    fn test_versions() {
+        let request = parse_ok(b"GET / HTTP/1.1\r\n\r\n");
+        assert!(request.version == Version::Http11);
+        assert!(request.keep_alive());
+
+        let request = parse_ok(b"GET / HTTP/1.0\r\n\r\n");
+        assert!(request.version == Version::Http10);
+        assert!(!request.keep_alive());
+
+        let request = parse_ok(b"GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n");
+        assert!(request.keep_alive());
+
+        for unsupported in [
+            &b"GET / HTTP/2.0\r\n\r\n"[..],
+            b"GET / HTTP/0.9\r\n\r\n",
+            b"GET /HTTP/1.1\r\n\r\n",
+            b"GET /\r\n\r\n",
+        ] {
+            assert!(matches!(
+                Request::from_buffer(unsupported),
+                ParsedRequest::Err(Status::HttpVersionNotSupported)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_target() {
+        let request = parse_ok(b"GET /img/my%20pic.png?size=big&utm=x HTTP/1.1\r\n\r\n");
+        assert_eq!(request.target, "/img/my%20pic.png?size=big&utm=x");
+        assert_eq!(request.path, "/img/my pic.png");
+        assert_eq!(request.query.get("size"), Some("big"));
+
+        for malformed in [
+            &b"GET  HTTP/1.1\r\n\r\n"[..],
+            b"GET /a%2Fb HTTP/1.1\r\n\r\n",
+            b"GET /%C3 HTTP/1.1\r\n\r\n",
+        ] {
+            assert!(matches!(
+                Request::from_buffer(malformed),
+                ParsedRequest::Err(Status::BadRequest)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_ranges() {
+        let ranges = |range: &str| {
+            let request = parse_ok(format!("GET / HTTP/1.1\r\nRange: {range}\r\n\r\n").as_bytes());
+            match request.ranges(10) {
+                Ranges::Absent => None,
+                Ranges::Satisfiable(ranges) => {
+                    Some(ranges.iter().map(|r| (r.start, r.end)).collect())
+                }
+                Ranges::Unsatisfiable => Some(Vec::new()),
+            }
+        };
+
+        assert!(matches!(
+            parse_ok(b"GET / HTTP/1.1\r\n\r\n").ranges(10),
+            Ranges::Absent
+        ));
+        assert_eq!(ranges("lines=1-2"), None);
+        assert_eq!(ranges("bytes=0-4"), Some(vec![(0, 5)]));
+        assert_eq!(ranges("bytes=5-"), Some(vec![(5, 10)]));
+        assert_eq!(ranges("bytes=-3"), Some(vec![(7, 10)]));
+        assert_eq!(ranges("bytes=8-20"), Some(vec![(8, 10)]));
+        assert_eq!(ranges("bytes=-20"), Some(vec![(0, 10)]));
+        assert_eq!(ranges("bytes=0-0, 20-30, -1"), Some(vec![(0, 1), (9, 10)]));
+        assert_eq!(ranges("bytes=10-"), Some(vec![]));
+        assert_eq!(ranges("bytes=-0"), Some(vec![]));
+        assert_eq!(ranges("bytes=4-2"), Some(vec![]));
+        assert_eq!(ranges("bytes=x-y"), Some(vec![]));
+    }
+
+    #[test]
+    fn test_headers() {
+        let request =
+            parse_ok(b"GET / HTTP/1.1\r\nHost: localhost\r\nif-none-match:  \"abc\"\r\n\r\n");
+        assert_eq!(request.headers.get("host"), Some("localhost"));
+        assert_eq!(request.headers.get("If-None-Match"), Some("\"abc\""));
+        assert_eq!(request.headers.get("Range"), None);
+        assert!(request.keep_alive());
+
+        let request = parse_ok(b"GET / HTTP/1.1\r\nConnection: Close\r\n\r\n");
+        assert!(!request.keep_alive());
+
+        for malformed in [
+            &b"GET / HTTP/1.1\r\nNo colon\r\n\r\n"[..],
+            b"GET / HTTP/1.1\r\nHost : localhost\r\n\r\n",
+            b"GET / HTTP/1.1\r\nHost: localhost\r\n folded\r\n\r\n",
+            b"GET / HTTP/1.1\r\nContent-Length: nope\r\n\r\n",
+            b"GET / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\n",
+        ] {
+            assert!(matches!(
+                Request::from_buffer(malformed),
+                ParsedRequest::Err(Status::BadRequest)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_pipelining() {
+        let buffer = b"GET /a HTTP/1.1\r\nContent-Length: 4\r\n\r\nbodyGET /b HTTP/1.1\r\n\r\n";
+        let (request, consumed) = match Request::from_buffer(buffer) {
+            ParsedRequest::Ok { request, consumed } => (request, consumed),
+            _ => panic!("request to be parsed"),
+        };
+        assert_eq!(request.target, "/a");
+        assert_eq!(request.body, b"body");
+
+        let request = parse_ok(&buffer[consumed..]);
+        assert_eq!(request.target, "/b");
+        assert!(request.body.is_empty());
+
+        assert!(matches!(
+            Request::from_buffer(&buffer[..consumed - 1]),
+            ParsedRequest::TooShort
+        ));
+    }
 }
@@ -1,48 +1,87 @@
-use super::{EOH, EOL, Status, VERSION};
+use super::{EOH, EOL, Status, Version};
+use crate::mime;
 use std::io::Write as _;
 
 pub struct Response {
+    pub version: Version,
     pub status: Status,
     pub content_type: &'static str,
+    pub headers: Vec<(String, String)>,
     pub body: Vec<u8>,
+    // Whether the body is the status phrase from `from_status`, which error pages may replace.
+    pub default_body: bool,
+    pub close: bool,
 }
 
 impl Response {
     pub fn from_status(status: Status) -> Self {
+        let mut headers = Vec::new();
+        if matches!(status, Status::MethodNotAllowed) {
+            headers.push((String::from("Allow"), String::from("GET, HEAD")));
+        }
         Self {
+            version: Version::Http11,
             status,
-            content_type: "text/plain",
+            content_type: mime::TEXT_PLAIN,
+            headers,
             body: status.phrase().as_bytes().to_vec(),
+            default_body: true,
+            close: false,
         }
     }
 
+    pub fn redirect(status: Status, location: String) -> Self {
+        let mut response = Self::from_status(status);
+        response.headers.push((String::from("Location"), location));
+        response
+    }
+
     pub fn closes(&self) -> bool {
-        !self.status.is_success() && !matches!(self.status, Status::NotFound)
+        self.close
+            || !self.status.is_success()
+                && !self.status.is_redirection()
+                && !matches!(self.status, Status::NotFound)
     }
 
-    pub fn serialize(&self) -> Vec<u8> {
-        let mut result = Vec::with_capacity(128 + self.body.len());
+    pub fn serialize_head(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(128);
 
-        result.extend_from_slice(VERSION);
+        result.extend_from_slice(self.version.as_bytes());
         write!(result, " {} ", self.status.code()).unwrap();
         result.extend_from_slice(self.status.phrase().as_bytes());
 
-        result.extend_from_slice(EOL);
-        write!(result, "Content-Length: {}", self.body.len()).unwrap();
+        // A 304's Content-Length would describe the cached representation, not the empty body.
+        if !matches!(self.status, Status::NotModified) {
+            result.extend_from_slice(EOL);
+            write!(result, "Content-Length: {}", self.body.len()).unwrap();
+        }
 
         if !self.content_type.is_empty() {
             result.extend_from_slice(EOL);
             write!(result, "Content-Type: {}", self.content_type).unwrap();
         }
 
+        for (name, value) in &self.headers {
+            result.extend_from_slice(EOL);
+            write!(result, "{name}: {value}").unwrap();
+        }
+
         if self.closes() {
             result.extend_from_slice(EOL);
             result.extend_from_slice(b"Connection: close");
+        } else if self.version == Version::Http10 {
+            result.extend_from_slice(EOL);
+            result.extend_from_slice(b"Connection: keep-alive");
         }
 
         result.extend_from_slice(EOH);
-        result.extend_from_slice(&self.body);
 
         result
     }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut result = self.serialize_head();
+        result.extend_from_slice(&self.body);
+        result
+    }
 }
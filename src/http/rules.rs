@@ -0,0 +1,405 @@
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+use super::{Status, headers};
+
+pub const HEADERS_FILE: &str = "_headers";
+pub const REDIRECTS_FILE: &str = "_redirects";
+
+pub struct HeaderRule {
+    pub pattern: String,
+    pub headers: Vec<(String, String)>,
+}
+
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+    pub status: Status,
+    // Forced redirects apply even when a file exists at the original path.
+    pub force: bool,
+}
+
+#[derive(Default)]
+pub struct Rules {
+    pub headers: Vec<HeaderRule>,
+    pub redirects: Vec<Redirect>,
+}
+
+pub struct Error {
+    pub path: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        } else {
+            write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+        }
+    }
+}
+
+fn validate_pattern(pattern: &str) -> Result<(), String> {
+    if !pattern.starts_with('/') {
+        return Err(format!(
+            "expected a path starting with '/', found '{pattern}'"
+        ));
+    }
+    let segments = pattern.split('/').collect::<Vec<_>>();
+    for (i, segment) in segments.iter().enumerate() {
+        if segment.contains('*') && (*segment != "*" || i + 1 != segments.len()) {
+            return Err(String::from("'*' may only be used as the last segment"));
+        }
+    }
+    Ok(())
+}
+
+// Returns the values of the placeholders in the pattern, including `splat` for `*`.
+fn match_pattern(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+    // Trailing slashes are not significant, so `/blog` and `/blog/` are the same.
+    let pattern = pattern.trim_end_matches('/');
+    let path = path.trim_end_matches('/');
+
+    let mut captures = Vec::new();
+    let mut path_segments = path.split('/');
+    for segment in pattern.split('/') {
+        if segment == "*" {
+            let rest = path_segments.collect::<Vec<_>>().join("/");
+            captures.push((String::from("splat"), rest));
+            return Some(captures);
+        }
+        let value = path_segments.next()?;
+        if let Some(name) = segment.strip_prefix(':') {
+            if value.is_empty() {
+                return None;
+            }
+            captures.push((name.to_owned(), value.to_owned()));
+        } else if segment != value {
+            return None;
+        }
+    }
+    path_segments.next().is_none().then_some(captures)
+}
+
+// The name of the placeholder whose colon is at `i`, if any.
+fn placeholder_at(text: &str, i: usize) -> Option<&str> {
+    let name = &text[i + 1..];
+    let name = &name[..name
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(name.len())];
+    // Also rules out the scheme and port of external destinations.
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        .then_some(name)
+}
+
+fn substitute(to: &str, captures: &[(String, String)]) -> String {
+    let mut result = String::with_capacity(to.len());
+    let mut last = 0;
+    for (i, _) in to.match_indices(':') {
+        if i < last {
+            continue;
+        }
+        if let Some(name) = placeholder_at(to, i)
+            && let Some((_, value)) = captures.iter().find(|(captured, _)| captured == name)
+        {
+            result.push_str(&to[last..i]);
+            result.push_str(value);
+            last = i + 1 + name.len();
+        }
+    }
+    result.push_str(&to[last..]);
+    result
+}
+
+fn parse_headers(path: &Path, text: &str, rules: &mut Rules, errors: &mut Vec<Error>) {
+    let mut error = |line: usize, message: String| {
+        errors.push(Error {
+            path: path.to_owned(),
+            line,
+            message,
+        })
+    };
+
+    let mut current = Option::<HeaderRule>::None;
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        // Paths start at the beginning of the line, and their headers are indented below.
+        if !line.starts_with([' ', '\t']) {
+            rules.headers.extend(current.take());
+            match validate_pattern(trimmed) {
+                Ok(()) => {
+                    current = Some(HeaderRule {
+                        pattern: trimmed.to_owned(),
+                        headers: Vec::new(),
+                    })
+                }
+                Err(message) => error(i + 1, message),
+            }
+            continue;
+        }
+
+        let rule = match current.as_mut() {
+            Some(rule) => rule,
+            None => {
+                error(i + 1, String::from("header is not under any path"));
+                continue;
+            }
+        };
+        match trimmed.split_once(':') {
+            Some((name, value)) if headers::is_valid_name(name.as_bytes()) => {
+                rule.headers
+                    .push((name.to_owned(), value.trim().to_owned()));
+            }
+            Some((name, _)) => error(i + 1, format!("invalid header name '{name}'")),
+            None => error(i + 1, String::from("expected 'Name: value'")),
+        }
+    }
+    rules.headers.extend(current);
+}
+
+fn parse_redirects(path: &Path, text: &str, rules: &mut Rules, errors: &mut Vec<Error>) {
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_redirect(line) {
+            Ok(redirect) => rules.redirects.push(redirect),
+            Err(message) => errors.push(Error {
+                path: path.to_owned(),
+                line: i + 1,
+                message,
+            }),
+        }
+    }
+}
+
+fn parse_redirect(line: &str) -> Result<Redirect, String> {
+    let mut tokens = line.split_whitespace();
+    let (from, to) = match (tokens.next(), tokens.next()) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return Err(String::from("expected a source and a destination")),
+    };
+    validate_pattern(from)?;
+
+    let (status, force) = match tokens.next() {
+        Some(status) => {
+            let (code, force) = match status.strip_suffix('!') {
+                Some(code) => (code, true),
+                None => (status, false),
+            };
+            let status = match code {
+                "200" => Status::Ok,
+                "301" => Status::MovedPermanently,
+                "302" => Status::Found,
+                "307" => Status::TemporaryRedirect,
+                "308" => Status::PermanentRedirect,
+                "404" => Status::NotFound,
+                _ => return Err(format!("unsupported status '{status}'")),
+            };
+            (status, force)
+        }
+        None => (Status::MovedPermanently, false),
+    };
+    if let Some(token) = tokens.next() {
+        return Err(format!("unsupported condition '{token}'"));
+    }
+
+    let external = to.starts_with("http://") || to.starts_with("https://");
+    if !to.starts_with('/') && !external {
+        return Err(format!("expected a path or URL destination, found '{to}'"));
+    }
+    if external && !status.is_redirection() {
+        return Err(format!(
+            "status {} needs a local destination",
+            status.code()
+        ));
+    }
+
+    // Every placeholder in the destination has to be captured by the source.
+    let captures = match_pattern(from, from).unwrap_or_default();
+    for name in to
+        .match_indices(':')
+        .filter_map(|(i, _)| placeholder_at(to, i))
+    {
+        if !captures.iter().any(|(captured, _)| captured == name) {
+            return Err(format!("placeholder ':{name}' is not in the source"));
+        }
+    }
+
+    Ok(Redirect {
+        from: from.to_owned(),
+        to: to.to_owned(),
+        status,
+        force,
+    })
+}
+
+impl Rules {
+    // Malformed rules are skipped and reported, so that the rest still apply.
+    pub fn load(folder: &Path) -> (Self, Vec<Error>) {
+        let mut rules = Self::default();
+        let mut errors = Vec::new();
+
+        for (name, parse) in [
+            (
+                HEADERS_FILE,
+                parse_headers as fn(&Path, &str, &mut Rules, &mut Vec<Error>),
+            ),
+            (REDIRECTS_FILE, parse_redirects),
+        ] {
+            let path = folder.join(name);
+            match fs::read_to_string(&path) {
+                Ok(text) => parse(&path, &text, &mut rules, &mut errors),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => errors.push(Error {
+                    path,
+                    line: 0,
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        (rules, errors)
+    }
+
+    pub fn headers_for(&self, path: &str) -> impl Iterator<Item = &(String, String)> {
+        self.headers
+            .iter()
+            .filter(move |rule| match_pattern(&rule.pattern, path).is_some())
+            .flat_map(|rule| &rule.headers)
+    }
+
+    // Returns the first matching redirect, along with its destination for this path.
+    pub fn redirect_for(&self, path: &str) -> Option<(&Redirect, String)> {
+        self.redirects.iter().find_map(|redirect| {
+            match_pattern(&redirect.from, path)
+                .map(|captures| (redirect, substitute(&redirect.to, &captures)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(headers: &str, redirects: &str) -> (Rules, Vec<String>) {
+        let mut rules = Rules::default();
+        let mut errors = Vec::new();
+        parse_headers(Path::new(HEADERS_FILE), headers, &mut rules, &mut errors);
+        parse_redirects(
+            Path::new(REDIRECTS_FILE),
+            redirects,
+            &mut rules,
+            &mut errors,
+        );
+        (rules, errors.iter().map(|e| e.to_string()).collect())
+    }
+
+    #[test]
+    fn test_match_pattern() {
+        assert_eq!(match_pattern("/", "/"), Some(vec![]));
+        assert_eq!(match_pattern("/blog", "/blog/"), Some(vec![]));
+        assert_eq!(match_pattern("/blog", "/blog/post"), None);
+        assert_eq!(
+            match_pattern("/blog/*", "/blog/a/b"),
+            Some(vec![(String::from("splat"), String::from("a/b"))])
+        );
+        assert_eq!(
+            match_pattern("/*", "/"),
+            Some(vec![(String::from("splat"), String::new())])
+        );
+        assert_eq!(
+            match_pattern("/tags/:tag", "/tags/rust/"),
+            Some(vec![(String::from("tag"), String::from("rust"))])
+        );
+        assert_eq!(match_pattern("/tags/:tag", "/tags/"), None);
+
+        let captures = [(String::from("tag"), String::from("x"))];
+        assert_eq!(
+            substitute("/:tagline/:tag?t=:tag", &captures),
+            "/:tagline/x?t=x"
+        );
+        assert_eq!(
+            substitute("https://a.com:80/:tag", &captures),
+            "https://a.com:80/x"
+        );
+    }
+
+    #[test]
+    fn test_headers() {
+        let (rules, errors) = parse(
+            "# comment\n/*\n  X-Frame-Options: DENY\n\n/blog/*\n  Cache-Control: max-age=60\n  X-Robots-Tag: none\n",
+            "",
+        );
+        assert!(errors.is_empty());
+        let headers = rules.headers_for("/blog/post/").collect::<Vec<_>>();
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers[1].0, "Cache-Control");
+        assert_eq!(headers[1].1, "max-age=60");
+        assert_eq!(rules.headers_for("/about").count(), 1);
+
+        let (_, errors) = parse(
+            "  X-Orphan: 1\nblog\n/ok\n  No colon\n  Bad Name: x\n/a*b\n",
+            "",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "_headers:1: header is not under any path",
+                "_headers:2: expected a path starting with '/', found 'blog'",
+                "_headers:4: expected 'Name: value'",
+                "_headers:5: invalid header name 'Bad Name'",
+                "_headers:6: '*' may only be used as the last segment",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redirects() {
+        let (rules, errors) = parse(
+            "",
+            "/old /new\n/news/* /blog/:splat 302\n/tags/:tag /blog/?tag=:tag 200!\n/gh https://github.com 307\n",
+        );
+        assert!(errors.is_empty());
+
+        let (redirect, to) = rules.redirect_for("/old/").unwrap();
+        assert!(matches!(redirect.status, Status::MovedPermanently));
+        assert!(!redirect.force);
+        assert_eq!(to, "/new");
+
+        let (redirect, to) = rules.redirect_for("/news/2024/post").unwrap();
+        assert!(matches!(redirect.status, Status::Found));
+        assert_eq!(to, "/blog/2024/post");
+
+        let (redirect, to) = rules.redirect_for("/tags/rust").unwrap();
+        assert!(matches!(redirect.status, Status::Ok));
+        assert!(redirect.force);
+        assert_eq!(to, "/blog/?tag=rust");
+
+        assert!(rules.redirect_for("/blog").is_none());
+
+        let (_, errors) = parse(
+            "",
+            "/only\n/a /b 418\n/a /b 301 Country=es\n/a b\n/a https://x.com 200\n/a /:missing\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "_redirects:1: expected a source and a destination",
+                "_redirects:2: unsupported status '418'",
+                "_redirects:3: unsupported condition 'Country=es'",
+                "_redirects:4: expected a path or URL destination, found 'b'",
+                "_redirects:5: status 200 needs a local destination",
+                "_redirects:6: placeholder ':missing' is not in the source",
+            ]
+        );
+    }
+}
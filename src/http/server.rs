@@ -1,51 +1,270 @@
-use std::io::{Read, Write as _};
-use std::net::{TcpListener, TcpStream};
-use std::path::PathBuf;
+use std::io::{IsTerminal as _, Read, Write as _};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{fs, io, thread};
 
-use super::{ParsedRequest, Request, Response, Status};
-use crate::conf;
+use super::pool::ThreadPool;
+use super::request::{REQUEST_BODY_MAX_SIZE, REQUEST_HEADERS_MAX_SIZE};
+use super::rules::{Redirect, Rules};
+use super::{
+    LiveReload, Method, ParsedRequest, Ranges, Request, Response, Status, Version, encode_path,
+    live_reload, shutdown,
+};
+use crate::site::Site;
+use crate::{date, gzip, html, mime};
 
-const REQUEST_HEADERS_MAX_SIZE: usize = 1024;
+const BUFFER_SIZE: usize = REQUEST_HEADERS_MAX_SIZE + REQUEST_BODY_MAX_SIZE;
+const WORKER_COUNT: usize = 8;
+const MAX_CONNECTIONS: usize = 64;
+// How long a keep-alive connection may sit without a request before it's closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+// How long a client may take to finish sending a request it has started.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+// Blocking calls wake up this often to notice a shutdown request.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
-pub fn run() {
-    let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+pub type Handler = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
 
-    for stream in listener.incoming() {
-        let stream = stream.expect("incomming connection to be alive");
-        thread::spawn(|| handle_connection(stream));
+#[derive(Default)]
+pub struct Options {
+    pub quiet: bool,
+    pub autoindex: bool,
+    pub live_reload: Option<Arc<LiveReload>>,
+    // Serves the site straight from memory instead of the root folder.
+    pub memory: Option<Arc<RwLock<Site>>>,
+    // Where to read the `_headers` and `_redirects` rules from, if anywhere.
+    pub rules_folder: Option<PathBuf>,
+    // Answers every request in place of the built-in file serving.
+    pub handler: Option<Handler>,
+}
+
+pub struct Server {
+    listener: TcpListener,
+    context: Arc<Context>,
+}
+
+#[derive(Clone)]
+pub struct StopHandle {
+    stop: Arc<AtomicBool>,
+}
+
+struct Context {
+    root: PathBuf,
+    options: Options,
+    stop: Arc<AtomicBool>,
+}
+
+impl Context {
+    fn stopping(&self) -> bool {
+        self.stop.load(Ordering::Relaxed) || shutdown::requested()
+    }
+}
+
+impl StopHandle {
+    // The server stops accepting right away, and returns once in-flight requests are done.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Server {
+    pub fn bind(
+        address: impl ToSocketAddrs,
+        root: impl Into<PathBuf>,
+        options: Options,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            context: Arc::new(Context {
+                root: root.into(),
+                options,
+                stop: Arc::new(AtomicBool::new(false)),
+            }),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle {
+            stop: Arc::clone(&self.context.stop),
+        }
+    }
+
+    pub fn run(self) {
+        let pool = ThreadPool::new(WORKER_COUNT);
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        while !self.context.stopping() {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(SHUTDOWN_POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("failed to accept connection: {e}");
+                    continue;
+                }
+            };
+
+            if stream
+                .set_nonblocking(false)
+                .and_then(|_| stream.set_read_timeout(Some(SHUTDOWN_POLL_INTERVAL)))
+                .and_then(|_| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+                .is_err()
+            {
+                continue;
+            }
+
+            // Queued connections count too, so that a burst can't pile up behind busy workers.
+            if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                connections.fetch_sub(1, Ordering::SeqCst);
+                let mut response = Response::from_status(Status::ServiceUnavailable);
+                response.close = true;
+                let _ = (&stream).write_all(&response.serialize());
+                continue;
+            }
+
+            let connections = Arc::clone(&connections);
+            let context = Arc::clone(&self.context);
+            pool.execute(move || {
+                handle_connection(stream, &context);
+                connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+
+        if !self.context.options.quiet {
+            println!("shutting down, waiting for in-flight requests");
+        }
+        drop(pool);
+    }
+}
+
+fn read_with_timeout(
+    stream: &mut TcpStream,
+    buffer: &mut [u8],
+    idle: bool,
+    context: &Context,
+) -> io::Result<usize> {
+    let timeout = if idle { IDLE_TIMEOUT } else { READ_TIMEOUT };
+    let start = Instant::now();
+    loop {
+        match stream.read(buffer) {
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                        | io::ErrorKind::Interrupted
+                ) =>
+            {
+                // Idle connections have nothing in flight, so they can be dropped right away.
+                if start.elapsed() >= timeout || idle && context.stopping() {
+                    return Err(io::ErrorKind::TimedOut.into());
+                }
+            }
+            result => return result,
+        }
     }
 }
 
-fn handle_connection(mut stream: TcpStream) {
+fn log_request(method: &str, target: &str, status: Status, bytes: usize, elapsed: Duration) {
+    let code = status.code();
+    let size = if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    };
+    let latency = format!("{:.1}ms", elapsed.as_secs_f64() * 1000.0);
+
+    if io::stdout().is_terminal() {
+        let color = match code {
+            200..300 => "32",
+            300..400 => "36",
+            400..500 => "33",
+            _ => "31",
+        };
+        println!("{method:<4} {target} \x1b[{color}m{code}\x1b[0m {size} \x1b[2m{latency}\x1b[0m");
+    } else {
+        println!("{method:<4} {target} {code} {size} {latency}");
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, context: &Context) {
     let mut read = 0;
-    let mut buffer = vec![0; REQUEST_HEADERS_MAX_SIZE];
+    let mut buffer = vec![0; BUFFER_SIZE];
 
     loop {
-        let response = match Request::from_buffer(&buffer[..read]) {
+        let mut head_only = false;
+        let start = Instant::now();
+        let mut method = "-";
+        let mut target = String::from("-");
+        let mut response = match Request::from_buffer(&buffer[..read]) {
             ParsedRequest::Ok { request, consumed } => {
                 buffer.copy_within(consumed..read, 0);
                 read -= consumed;
-                handle_request(request)
+                if let Some(live_reload) = &context.options.live_reload
+                    && request.path == live_reload::ENDPOINT
+                {
+                    live_reload.subscribe(stream);
+                    return;
+                }
+                head_only = request.method == Method::Head;
+                method = request.method.as_str();
+                target = request.target.clone();
+                let mut response = handle_request(&request, context);
+                response.version = request.version;
+                response.close = !request.keep_alive();
+                response
             }
             ParsedRequest::Err(status) => Response::from_status(status),
-            ParsedRequest::TooShort if read == REQUEST_HEADERS_MAX_SIZE => {
-                Response::from_status(Status::RequestHeaderFieldsTooLarge)
-            }
-            ParsedRequest::TooShort => match stream.read(&mut buffer[read..]) {
-                Ok(0) => return,
-                Ok(n) => {
-                    read += n;
-                    continue;
-                }
-                Err(e) if e.kind() == io::ErrorKind::TimedOut => {
-                    Response::from_status(Status::RequestTimeout)
+            ParsedRequest::TooShort => {
+                match read_with_timeout(&mut stream, &mut buffer[read..], read == 0, context) {
+                    Ok(0) => return,
+                    Ok(n) => {
+                        read += n;
+                        continue;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut && read == 0 => return,
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                        Response::from_status(Status::RequestTimeout)
+                    }
+                    Err(_) => Response::from_status(Status::BadRequest),
                 }
-                Err(_) => Response::from_status(Status::BadRequest),
-            },
+            }
+        };
+        if context.stopping() {
+            response.close = true;
+        }
+        // Only the generic bodies are replaced, not those already chosen by a rule or handler.
+        if response.default_body && response.status.code() >= 400 {
+            apply_error_page(&mut response, context);
+        }
+
+        let serialized = if head_only {
+            response.serialize_head()
+        } else {
+            response.serialize()
         };
 
-        match stream.write_all(&response.serialize()) {
+        let written = stream.write_all(&serialized);
+        if !context.options.quiet {
+            let bytes = if head_only { 0 } else { response.body.len() };
+            log_request(method, &target, response.status, bytes, start.elapsed());
+        }
+
+        match written {
             Ok(_) => {
                 if response.closes() {
                     return;
@@ -54,7 +273,11 @@ fn handle_connection(mut stream: TcpStream) {
             Err(e) => {
                 if !matches!(
                     e.kind(),
-                    io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted
+                    io::ErrorKind::ConnectionReset
+                        | io::ErrorKind::ConnectionAborted
+                        | io::ErrorKind::BrokenPipe
+                        | io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
                 ) {
                     eprintln!(
                         "unexpected failure while writing response: {e} {}",
@@ -67,41 +290,404 @@ fn handle_connection(mut stream: TcpStream) {
     }
 }
 
-fn handle_request(request: Request) -> Response {
-    let root = match PathBuf::from(conf::OUTPUT_FOLDER).canonicalize() {
+struct Resource {
+    path: PathBuf,
+    contents: Vec<u8>,
+    modified: Option<SystemTime>,
+}
+
+// Relative links inside the index only resolve correctly with the trailing slash.
+fn redirect_to_directory(request: &Request, path: &str) -> Response {
+    let mut location = encode_path(path);
+    location.push('/');
+    if !request.query.as_str().is_empty() {
+        location.push('?');
+        location.push_str(request.query.as_str());
+    }
+    Response::redirect(Status::MovedPermanently, location)
+}
+
+fn resolve_file(request: &Request, path: &str, context: &Context) -> Result<Resource, Response> {
+    let root = match context.root.canonicalize() {
         Ok(root) => root,
-        Err(_) => return Response::from_status(Status::NotFound),
+        Err(_) => return Err(Response::from_status(Status::NotFound)),
     };
 
-    let path = match root.join(&request.target[1..]).canonicalize() {
+    let request_path = path;
+    let mut path = match root.join(&request_path[1..]).canonicalize() {
         Ok(x) if x.starts_with(&root) => x,
-        _ => return Response::from_status(Status::NotFound),
+        _ => return Err(Response::from_status(Status::NotFound)),
     };
 
+    if path.is_dir() {
+        if !request_path.ends_with('/') {
+            return Err(redirect_to_directory(request, request_path));
+        }
+        path.push("index.html");
+        if context.options.autoindex && !path.exists() {
+            path.pop();
+            return Err(match read_directory(&path) {
+                Ok(names) => directory_listing(names, request_path),
+                Err(e) => response_from_error_kind(e.kind()),
+            });
+        }
+    }
+
     let contents = match fs::read(&path) {
         Ok(x) => x,
-        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
-            // Probably tried to read a directory as a file
-            match fs::read(path.join("index.html")) {
-                Ok(x) => x,
-                Err(e) => return response_from_error_kind(e.kind()),
+        Err(e) => return Err(response_from_error_kind(e.kind())),
+    };
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+
+    Ok(Resource {
+        path,
+        contents,
+        modified,
+    })
+}
+
+fn resolve_entry(
+    request: &Request,
+    path: &str,
+    site: &Site,
+    context: &Context,
+) -> Result<Resource, Response> {
+    // Only exact matches are looked up, so there's no way to escape the site.
+    let request_path = path;
+    let mut path = PathBuf::from(&request_path[1..]);
+    if request_path.ends_with('/') {
+        path.push("index.html");
+        if site.find(&path).is_none() {
+            path.pop();
+            let names = site.list(&path);
+            return Err(if context.options.autoindex && !names.is_empty() {
+                directory_listing(names, request_path)
+            } else {
+                Response::from_status(Status::NotFound)
+            });
+        }
+    }
+
+    match site.find(&path) {
+        Some(entry) => match site.render(entry) {
+            Ok(contents) => Ok(Resource {
+                contents,
+                path,
+                modified: None,
+            }),
+            Err(error) => {
+                let mut response = Response::from_status(Status::InternalServerError);
+                response.content_type = mime::TEXT_HTML;
+                response.body = b"<pre>".to_vec();
+                response.default_body = false;
+                response
+                    .body
+                    .extend(html::escape(error.to_string().as_bytes()));
+                response.body.extend(b"</pre>");
+                if let Some(live_reload) = &context.options.live_reload {
+                    live_reload.inject(&mut response.body);
+                }
+                Err(response)
+            }
+        },
+        None if !site.list(&path).is_empty() => Err(redirect_to_directory(request, request_path)),
+        None => Err(Response::from_status(Status::NotFound)),
+    }
+}
+
+fn handle_request(request: &Request, context: &Context) -> Response {
+    if let Some(handler) = &context.options.handler {
+        return handler(request);
+    }
+
+    // Read on every request, so that edits show up without restarting.
+    let (rules, _) = match &context.options.rules_folder {
+        Some(folder) => Rules::load(folder),
+        None => Default::default(),
+    };
+
+    let mut response = match rules.redirect_for(&request.path) {
+        Some((redirect, to)) if redirect.force => apply_redirect(request, redirect, &to, context),
+        Some((redirect, to)) => {
+            let response = serve_path(request, &request.path, context);
+            if matches!(response.status, Status::NotFound) {
+                apply_redirect(request, redirect, &to, context)
+            } else {
+                response
             }
         }
-        Err(e) => return response_from_error_kind(e.kind()),
+        None => serve_path(request, &request.path, context),
     };
 
+    for (name, value) in rules.headers_for(&request.path) {
+        response
+            .headers
+            .retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        response.headers.push((name.clone(), value.clone()));
+    }
+    response
+}
+
+fn apply_redirect(request: &Request, redirect: &Redirect, to: &str, context: &Context) -> Response {
+    match redirect.status {
+        // Rewrites and custom not found pages are served from the destination, without redirecting.
+        Status::Ok | Status::NotFound => {
+            let path = to.split_once('?').map_or(to, |(path, _)| path);
+            let mut response = serve_path(request, path, context);
+            if matches!(response.status, Status::Ok) {
+                response.status = redirect.status;
+            }
+            response
+        }
+        status => {
+            let mut location = to.to_owned();
+            if !location.contains('?') && !request.query.as_str().is_empty() {
+                location.push('?');
+                location.push_str(request.query.as_str());
+            }
+            Response::redirect(status, location)
+        }
+    }
+}
+
+fn serve_path(request: &Request, path: &str, context: &Context) -> Response {
+    let resolved = match &context.options.memory {
+        Some(site) => resolve_entry(
+            request,
+            path,
+            &site.read().expect("site lock to not be poisoned"),
+            context,
+        ),
+        None => resolve_file(request, path, context),
+    };
+    let resource = match resolved {
+        Ok(resource) => resource,
+        Err(response) => return response,
+    };
+
+    let mut response = Response {
+        version: Version::Http11,
+        status: Status::Ok,
+        content_type: mime::from_path(&resource.path, &resource.contents),
+        headers: Vec::new(),
+        body: resource.contents,
+        default_body: false,
+        close: false,
+    };
+
+    if let Some(live_reload) = &context.options.live_reload
+        && mime::essence(response.content_type) == "text/html"
+    {
+        live_reload.inject(&mut response.body);
+    }
+
+    let compressible = mime::is_compressible(response.content_type);
+    let gzip = compressible && request.accepts_encoding("gzip");
+    let mut etag = entity_tag(&response.body);
+    if gzip {
+        // Each encoding is a different representation, so it needs its own tag.
+        etag.insert_str(etag.len() - 1, "-gzip");
+    }
+    let modified = resource.modified;
+    if is_not_modified(request, &etag, modified) {
+        response.status = Status::NotModified;
+        response.content_type = "";
+        response.body.clear();
+    } else if gzip {
+        response.body = gzip::encode(&response.body);
+        response
+            .headers
+            .push((String::from("Content-Encoding"), String::from("gzip")));
+    }
+    if compressible {
+        response
+            .headers
+            .push((String::from("Vary"), String::from("Accept-Encoding")));
+    }
+
+    let last_modified = modified.map(date::system_time_to_http_date);
+    if matches!(response.status, Status::Ok)
+        && request
+            .headers
+            .get("If-Range")
+            .is_none_or(|v| v == etag || Some(v) == last_modified.as_deref())
+    {
+        response = apply_ranges(request, response);
+    }
+
+    response
+        .headers
+        .push((String::from("Accept-Ranges"), String::from("bytes")));
+    response.headers.push((String::from("ETag"), etag));
+    if let Some(last_modified) = last_modified {
+        response
+            .headers
+            .push((String::from("Last-Modified"), last_modified));
+    }
+
+    response
+}
+
+fn apply_error_page(response: &mut Response, context: &Context) {
+    let name = format!("{}.html", response.status.code());
+    let body = match &context.options.memory {
+        Some(site) => {
+            let site = site.read().expect("site lock to not be poisoned");
+            site.find(Path::new(&name))
+                .and_then(|entry| site.render(entry).ok())
+        }
+        None => fs::read(context.root.join(name)).ok(),
+    };
+    if let Some(body) = body {
+        response.content_type = mime::TEXT_HTML;
+        response.body = body;
+        response.default_body = false;
+        if let Some(live_reload) = &context.options.live_reload {
+            live_reload.inject(&mut response.body);
+        }
+    }
+}
+
+// Returns whether each name is a directory along with the name.
+fn read_directory(dir: &Path) -> io::Result<Vec<(bool, String)>> {
+    Ok(fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            (entry.file_type().is_ok_and(|t| t.is_dir()), name)
+        })
+        .collect())
+}
+
+fn directory_listing(names: Vec<(bool, String)>, request_path: &str) -> Response {
+    let mut names = names
+        .into_iter()
+        .map(|(is_dir, name)| {
+            if is_dir {
+                (false, format!("{name}/"))
+            } else {
+                (true, name)
+            }
+        })
+        .collect::<Vec<_>>();
+    // Directories first, then files, each sorted by name.
+    names.sort();
+    if request_path != "/" {
+        names.insert(0, (false, String::from("../")));
+    }
+
+    let title = html::escape(format!("Index of {request_path}").as_bytes());
+    let mut body = Vec::new();
+    body.extend_from_slice(b"<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>");
+    body.extend_from_slice(&title);
+    body.extend_from_slice(b"</title></head><body><h1>");
+    body.extend_from_slice(&title);
+    body.extend_from_slice(b"</h1><ul>");
+    for (_, name) in names {
+        body.extend_from_slice(b"<li><a href=\"");
+        // Otherwise a name with a colon would be read as a URL scheme.
+        if name.contains(':') {
+            body.extend_from_slice(b"./");
+        }
+        body.extend(html::escape_attribute(encode_path(&name).bytes()));
+        body.extend_from_slice(b"\">");
+        body.extend(html::escape(name.as_bytes()));
+        body.extend_from_slice(b"</a></li>");
+    }
+    body.extend_from_slice(b"</ul></body></html>\n");
+
     Response {
+        version: Version::Http11,
         status: Status::Ok,
-        content_type: match path.extension().and_then(|e| e.to_str()) {
-            Some("css") => "text/css",
-            Some("html") => "text/html",
-            Some("ico") => "image/x-icon",
-            Some("js") => "text/javascript",
-            Some("png") => "image/png",
-            Some("svg") => "image/svg+xml",
-            _ => "",
-        },
-        body: contents,
+        content_type: mime::TEXT_HTML,
+        headers: Vec::new(),
+        body,
+        default_body: false,
+        close: false,
+    }
+}
+
+fn apply_ranges(request: &Request, mut response: Response) -> Response {
+    const BOUNDARY: &str = "site-byteranges-boundary";
+    const MULTIPART_CONTENT_TYPE: &str = "multipart/byteranges; boundary=site-byteranges-boundary";
+    let length = response.body.len();
+
+    match request.ranges(length) {
+        Ranges::Absent => response,
+        Ranges::Unsatisfiable => {
+            let mut response = Response::from_status(Status::RangeNotSatisfiable);
+            response
+                .headers
+                .push((String::from("Content-Range"), format!("bytes */{length}")));
+            response
+        }
+        Ranges::Satisfiable(ranges) if ranges.len() == 1 => {
+            let range = ranges[0].clone();
+            response.status = Status::PartialContent;
+            response.headers.push((
+                String::from("Content-Range"),
+                format!("bytes {}-{}/{length}", range.start, range.end - 1),
+            ));
+            response.body.truncate(range.end);
+            response.body.drain(..range.start);
+            response
+        }
+        Ranges::Satisfiable(ranges) => {
+            let mut body = Vec::new();
+            for range in ranges {
+                write!(body, "--{BOUNDARY}\r\n").unwrap();
+                if !response.content_type.is_empty() {
+                    write!(body, "Content-Type: {}\r\n", response.content_type).unwrap();
+                }
+                write!(
+                    body,
+                    "Content-Range: bytes {}-{}/{length}\r\n\r\n",
+                    range.start,
+                    range.end - 1
+                )
+                .unwrap();
+                body.extend_from_slice(&response.body[range]);
+                body.extend_from_slice(b"\r\n");
+            }
+            write!(body, "--{BOUNDARY}--\r\n").unwrap();
+
+            response.status = Status::PartialContent;
+            response.content_type = MULTIPART_CONTENT_TYPE;
+            response.body = body;
+            response
+        }
+    }
+}
+
+fn entity_tag(contents: &[u8]) -> String {
+    // FNV-1a, which is plenty to tell apart versions of the same file.
+    let hash = contents.iter().fold(0xcbf29ce484222325u64, |hash, &c| {
+        (hash ^ u64::from(c)).wrapping_mul(0x100000001b3)
+    });
+    format!("\"{hash:016x}\"")
+}
+
+fn is_not_modified(request: &Request, etag: &str, modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = request.headers.get("If-None-Match") {
+        // If-Modified-Since must be ignored when If-None-Match is present.
+        if_none_match
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+    } else if let Some(since) = request
+        .headers
+        .get("If-Modified-Since")
+        .and_then(date::http_date_to_system_time)
+        && let Some(modified) = modified
+    {
+        // HTTP dates have a resolution of seconds.
+        let modified = modified
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let since = since.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        modified <= since
+    } else {
+        false
     }
 }
 
@@ -111,3 +697,113 @@ fn response_from_error_kind(error_kind: io::ErrorKind) -> Response {
         _ => Response::from_status(Status::ServiceUnavailable),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    fn get(address: SocketAddr, target: &str) -> String {
+        let mut stream = TcpStream::connect(address).expect("server to accept connections");
+        write!(stream, "GET {target} HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn spawn(root: &Path, options: Options) -> (SocketAddr, StopHandle, thread::JoinHandle<()>) {
+        let server = Server::bind("127.0.0.1:0", root, options).expect("ephemeral port to bind");
+        let address = server.local_addr().expect("bound address to be known");
+        let stop = server.stop_handle();
+        (address, stop, thread::spawn(move || server.run()))
+    }
+
+    #[test]
+    fn test_directory_listing() {
+        let names = vec![
+            (false, String::from("b.txt")),
+            (true, String::from("z dir")),
+            (false, String::from("a&<b>.html")),
+            (false, String::from("c:d")),
+            (true, String::from("a")),
+        ];
+        let response = directory_listing(names, "/<x>/");
+        let body = String::from_utf8(response.body).unwrap();
+        assert!(body.contains("<title>Index of /&lt;x&gt;/</title>"));
+        assert!(body.contains(
+            "<ul><li><a href=\"../\">../</a></li>\
+            <li><a href=\"a/\">a/</a></li>\
+            <li><a href=\"z%20dir/\">z dir/</a></li>\
+            <li><a href=\"a&amp;%3Cb%3E.html\">a&amp;&lt;b&gt;.html</a></li>\
+            <li><a href=\"b.txt\">b.txt</a></li>\
+            <li><a href=\"./c:d\">c:d</a></li></ul>"
+        ));
+
+        let body = directory_listing(Vec::new(), "/").body;
+        assert!(String::from_utf8(body).unwrap().contains("<ul></ul>"));
+    }
+
+    #[test]
+    fn test_server() {
+        let root = env::temp_dir().join(format!("site-server-test-{}", process::id()));
+        fs::create_dir_all(root.join("blog")).unwrap();
+        fs::write(root.join("index.html"), "<p>home</p>").unwrap();
+
+        let options = Options {
+            quiet: true,
+            ..Default::default()
+        };
+        let (address, stop, server) = spawn(&root, options);
+        assert_ne!(address.port(), 0);
+
+        let response = get(address, "/");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n<p>home</p>"));
+        assert!(get(address, "/blog").starts_with("HTTP/1.1 301 "));
+        assert!(get(address, "/blog/").starts_with("HTTP/1.1 404 "));
+
+        stop.stop();
+        server.join().expect("server to stop cleanly");
+        assert!(TcpStream::connect(address).is_err());
+
+        let options = Options {
+            quiet: true,
+            handler: Some(Arc::new(|request: &Request| {
+                let mut response = Response::from_status(Status::Ok);
+                response.body = request.path.as_bytes().to_vec();
+                response
+            })),
+            ..Default::default()
+        };
+        let (address, stop, server) = spawn(&root, options);
+        assert!(get(address, "/anything").ends_with("\r\n\r\n/anything"));
+        stop.stop();
+        server.join().expect("server to stop cleanly");
+
+        // Only the default bodies are replaced by the custom error page.
+        fs::write(root.join("404.html"), "<p>custom</p>").unwrap();
+        let options = Options {
+            quiet: true,
+            handler: Some(Arc::new(|request: &Request| {
+                let mut response = Response::from_status(Status::NotFound);
+                if request.path == "/chosen" {
+                    response.default_body = false;
+                }
+                response
+            })),
+            ..Default::default()
+        };
+        let (address, stop, server) = spawn(&root, options);
+        let response = get(address, "/missing");
+        assert!(response.starts_with("HTTP/1.1 404 "));
+        assert!(response.contains("Content-Type: text/html; charset=utf-8\r\n"));
+        assert!(response.ends_with("\r\n\r\n<p>custom</p>"));
+        let response = get(address, "/chosen");
+        assert!(response.contains("Content-Type: text/plain; charset=utf-8\r\n"));
+        assert!(response.ends_with("\r\n\r\nNot Found"));
+        stop.stop();
+        server.join().expect("server to stop cleanly");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+#[cfg(target_os = "linux")]
+pub fn install_handler() {
+    use crate::libc;
+
+    extern "C" fn handler(_signum: i32) {
+        REQUESTED.store(true, Ordering::Relaxed);
+    }
+
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+#[cfg(windows)]
+pub fn install_handler() {
+    use crate::winapi;
+
+    unsafe extern "system" fn handler(_ctrl_type: i32) -> i32 {
+        REQUESTED.store(true, Ordering::Relaxed);
+        1
+    }
+
+    unsafe {
+        winapi::SetConsoleCtrlHandler(handler, 1);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn install_handler() {
+    // Without a handler, Ctrl+C simply terminates the process.
+}
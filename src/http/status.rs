@@ -2,11 +2,21 @@
 #[derive(Clone, Copy)]
 pub enum Status {
     Ok = 200,
+    PartialContent = 206,
+    MovedPermanently = 301,
+    Found = 302,
+    NotModified = 304,
+    TemporaryRedirect = 307,
+    PermanentRedirect = 308,
     BadRequest = 400,
     NotFound = 404,
     MethodNotAllowed = 405,
     RequestTimeout = 408,
+    ContentTooLarge = 413,
+    RangeNotSatisfiable = 416,
     RequestHeaderFieldsTooLarge = 431,
+    InternalServerError = 500,
+    NotImplemented = 501,
     ServiceUnavailable = 503,
     HttpVersionNotSupported = 505,
 }
@@ -19,11 +29,21 @@ impl Status {
     pub fn phrase(&self) -> &'static str {
         match self {
             Self::Ok => "OK",
-            Self::BadRequest => "400 Bad Request",
+            Self::PartialContent => "Partial Content",
+            Self::MovedPermanently => "Moved Permanently",
+            Self::Found => "Found",
+            Self::NotModified => "Not Modified",
+            Self::TemporaryRedirect => "Temporary Redirect",
+            Self::PermanentRedirect => "Permanent Redirect",
+            Self::BadRequest => "Bad Request",
             Self::NotFound => "Not Found",
             Self::MethodNotAllowed => "Method Not Allowed",
             Self::RequestTimeout => "Request Timeout",
+            Self::ContentTooLarge => "Content Too Large",
+            Self::RangeNotSatisfiable => "Range Not Satisfiable",
             Self::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
+            Self::InternalServerError => "Internal Server Error",
+            Self::NotImplemented => "Not Implemented",
             Self::ServiceUnavailable => "Service Unavailable",
             Self::HttpVersionNotSupported => "HTTP Version Not Supported",
         }
@@ -32,4 +52,8 @@ impl Status {
     pub fn is_success(&self) -> bool {
         (200..300).contains(&self.code())
     }
+
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.code())
+    }
 }
@@ -0,0 +1,211 @@
+use super::Status;
+
+#[derive(Default)]
+pub struct Query {
+    raw: String,
+    params: Vec<(String, String)>,
+}
+
+pub struct Target {
+    pub path: String,
+    pub query: Query,
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn percent_decode(text: &[u8], plus_is_space: bool) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(text.len());
+    let mut i = 0;
+    while i < text.len() {
+        match text[i] {
+            b'%' => {
+                let high = hex_value(*text.get(i + 1)?)?;
+                let low = hex_value(*text.get(i + 2)?)?;
+                result.push(high << 4 | low);
+                i += 3;
+            }
+            b'+' if plus_is_space => {
+                result.push(b' ');
+                i += 1;
+            }
+            c => {
+                result.push(c);
+                i += 1;
+            }
+        }
+    }
+    Some(result)
+}
+
+pub fn encode_path(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    for c in path.bytes() {
+        if c.is_ascii_alphanumeric() || b"/-._~!$&'()*+,;=:@".contains(&c) {
+            result.push(c as char);
+        } else {
+            result.push_str(&format!("%{c:02X}"));
+        }
+    }
+    result
+}
+
+fn decode_path(path: &[u8]) -> Option<String> {
+    // An encoded slash would be indistinguishable from a real separator once decoded,
+    // and a nul byte cannot be part of any file name.
+    if path
+        .windows(3)
+        .any(|w| w[0] == b'%' && w[1] == b'2' && w[2].eq_ignore_ascii_case(&b'f'))
+    {
+        return None;
+    }
+    let decoded = percent_decode(path, false)?;
+    if decoded.contains(&0) {
+        return None;
+    }
+    String::from_utf8(decoded).ok()
+}
+
+impl Query {
+    fn parse(raw: &str) -> Self {
+        let params = raw
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .map(|param| {
+                let (name, value) = param.split_once('=').unwrap_or((param, ""));
+                let decode = |text: &str| {
+                    let decoded = percent_decode(text.as_bytes(), true)
+                        .unwrap_or_else(|| text.as_bytes().to_vec());
+                    String::from_utf8_lossy(&decoded).into_owned()
+                };
+                (decode(name), decode(value))
+            })
+            .collect();
+
+        Self {
+            raw: raw.to_owned(),
+            params,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+impl Target {
+    pub fn parse(target: &[u8]) -> Result<Self, Status> {
+        // Absolute-form targets are meant for proxies, but must be accepted by servers too.
+        let origin_form = if target.starts_with(b"/") {
+            target
+        } else if let Some(rest) = target
+            .strip_prefix(b"http://")
+            .or_else(|| target.strip_prefix(b"https://"))
+        {
+            let i = rest
+                .iter()
+                .position(|&c| matches!(c, b'/' | b'?' | b'#'))
+                .unwrap_or(rest.len());
+            match &rest[i..] {
+                [b'/', ..] => &rest[i..],
+                _ => return Self::parse(&[b"/", &rest[i..]].concat()),
+            }
+        } else {
+            return Err(Status::BadRequest);
+        };
+
+        let end = origin_form
+            .iter()
+            .position(|&c| c == b'#')
+            .unwrap_or(origin_form.len());
+        let without_fragment = &origin_form[..end];
+        let (path, query) = match without_fragment.iter().position(|&c| c == b'?') {
+            Some(i) => (&without_fragment[..i], &without_fragment[i + 1..]),
+            None => (without_fragment, &b""[..]),
+        };
+
+        let path = decode_path(path).ok_or(Status::BadRequest)?;
+        let query = Query::parse(&String::from_utf8_lossy(query));
+        Ok(Self { path, query })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(target: &str) -> Option<(String, String)> {
+        Target::parse(target.as_bytes())
+            .ok()
+            .map(|t| (t.path, t.query.as_str().to_owned()))
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(parse("/"), Some(("/".into(), "".into())));
+        assert_eq!(
+            parse("/blog/?utm=x"),
+            Some(("/blog/".into(), "utm=x".into()))
+        );
+        assert_eq!(
+            parse("/img/my%20pic.png#top"),
+            Some(("/img/my pic.png".into(), "".into()))
+        );
+        assert_eq!(parse("/caf%C3%A9"), Some(("/café".into(), "".into())));
+        assert_eq!(
+            parse("http://localhost:8080/blog?a=b"),
+            Some(("/blog".into(), "a=b".into()))
+        );
+        assert_eq!(parse("http://localhost"), Some(("/".into(), "".into())));
+        assert_eq!(parse("http://localhost?a"), Some(("/".into(), "a".into())));
+
+        assert_eq!(parse("*"), None);
+        assert_eq!(parse("blog"), None);
+        assert_eq!(parse("/a%2Fb"), None);
+        assert_eq!(parse("/a%2fb"), None);
+        assert_eq!(parse("/a%00"), None);
+        assert_eq!(parse("/%FF"), None);
+        assert_eq!(parse("/%4"), None);
+        assert_eq!(parse("/%zz"), None);
+    }
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path("/blog/woce-1/"), "/blog/woce-1/");
+        assert_eq!(encode_path("/img/my pic.png"), "/img/my%20pic.png");
+        assert_eq!(encode_path("/café?#"), "/caf%C3%A9%3F%23");
+        for path in ["/img/my pic.png", "/café?#", "/100%"] {
+            assert_eq!(parse(&encode_path(path)).unwrap().0, path);
+        }
+    }
+
+    #[test]
+    fn test_query() {
+        let query = Query::parse("a=1&b=two+words&c=%26&flag&&a=2");
+        assert_eq!(query.get("a"), Some("1"));
+        assert_eq!(query.get("b"), Some("two words"));
+        assert_eq!(query.get("c"), Some("&"));
+        assert_eq!(query.get("flag"), Some(""));
+        assert_eq!(query.get("missing"), None);
+        assert_eq!(query.iter().count(), 5);
+    }
+}
@@ -0,0 +1,42 @@
+#![allow(non_camel_case_types)]
+use std::ffi::{c_char, c_void};
+
+pub const IN_CLOEXEC: i32 = 0o2000000;
+pub const IN_CLOSE_WRITE: u32 = 0x00000008;
+pub const IN_CREATE: u32 = 0x00000100;
+pub const IN_DELETE: u32 = 0x00000200;
+pub const IN_DELETE_SELF: u32 = 0x00000400;
+pub const IN_IGNORED: u32 = 0x00008000;
+pub const IN_ISDIR: u32 = 0x40000000;
+pub const IN_MOVED_FROM: u32 = 0x00000040;
+pub const IN_MOVED_TO: u32 = 0x00000080;
+pub const IN_ONLYDIR: u32 = 0x01000000;
+pub const IN_Q_OVERFLOW: u32 = 0x00004000;
+pub const SIGINT: i32 = 2;
+pub const SIGTERM: i32 = 15;
+
+pub type sighandler_t = extern "C" fn(i32);
+
+#[repr(C)]
+pub struct inotify_event {
+    pub wd: i32,
+    pub mask: u32,
+    pub cookie: u32,
+    pub len: u32,
+    pub name: [c_char; 0],
+}
+
+#[link(name = "c")]
+unsafe extern "C" {
+    pub fn inotify_init1(flags: i32) -> i32;
+
+    pub fn inotify_add_watch(fd: i32, pathname: *const c_char, mask: u32) -> i32;
+
+    pub fn inotify_rm_watch(fd: i32, wd: i32) -> i32;
+
+    pub fn read(fd: i32, buf: *mut c_void, count: usize) -> isize;
+
+    pub fn close(fd: i32) -> i32;
+
+    pub fn signal(signum: i32, handler: sighandler_t) -> usize;
+}
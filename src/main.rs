@@ -1,5 +1,7 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::{Arc, RwLock};
 use std::{fs, thread};
 
 pub mod cli;
@@ -10,9 +12,14 @@ pub mod date;
 pub mod entry;
 pub mod feed;
 pub mod file_watcher;
+pub mod gzip;
 pub mod html;
 pub mod http;
+#[cfg(target_os = "linux")]
+pub mod libc;
 pub mod markdown;
+pub mod mime;
+pub mod site;
 pub mod template;
 pub mod toml;
 pub mod walkdir;
@@ -22,11 +29,8 @@ pub mod xml;
 
 use cli::BuildConfig;
 use entry::Entry;
-
-fn load_template() -> Vec<u8> {
-    let path = PathBuf::from(conf::INPUT_FOLDER).join(conf::TEMPLATE_NAME);
-    html::minify(&fs::read(path).expect("path to be a readable file"))
-}
+use http::{LiveReload, live_reload};
+use site::Site;
 
 fn commit_file(path: &Path, contents: &[u8]) {
     fs::create_dir_all(path.parent().expect("path to have a parent"))
@@ -35,7 +39,7 @@ fn commit_file(path: &Path, contents: &[u8]) {
     fs::write(path, contents).expect("path to be writable");
 }
 
-fn build(config: cli::BuildConfig) {
+fn load_entries(ignore_errors: bool) -> Vec<Entry> {
     let mut entries = Vec::<Entry>::new();
 
     entries.push(Entry::from_new_path_with_contents(
@@ -43,7 +47,6 @@ fn build(config: cli::BuildConfig) {
         conf::CNAME.as_bytes().to_vec(),
     ));
 
-    let template = load_template();
     for dir_entry in walkdir::walk(PathBuf::from(conf::INPUT_FOLDER)) {
         if dir_entry.file_name() == conf::TEMPLATE_NAME {
             continue;
@@ -55,7 +58,7 @@ fn build(config: cli::BuildConfig) {
             }
             Err(error) => {
                 println!("failed to process file: {:?}\n  {error}", dir_entry.path());
-                if config.ignore_errors {
+                if ignore_errors {
                     continue;
                 }
                 process::exit(1);
@@ -63,27 +66,57 @@ fn build(config: cli::BuildConfig) {
         }
     }
 
-    entries.push(Entry::from_new_path_with_contents(
-        PathBuf::from("blog/atom.xml"),
-        feed::from_markdown_entries(entries.iter().filter(|entry| {
-            entry.path.extension().is_some_and(|e| e == "md")
-                && entry.path.file_stem().is_none_or(|s| s != "_index")
-                && entry.path_parent() == Some(&PathBuf::from(conf::INPUT_FOLDER).join("blog"))
-        }))
-        .to_string()
-        .into_bytes(),
-    ));
+    entries
+}
+
+fn load_site(ignore_errors: bool) -> Site {
+    let template = site::load_template().expect("template to be a readable file");
+    Site::new(template, load_entries(ignore_errors))
+}
+
+fn check_rules() -> bool {
+    let (_, errors) = http::rules::Rules::load(Path::new(conf::INPUT_FOLDER));
+    for error in &errors {
+        println!("failed to process rule: {error}");
+    }
+    errors.is_empty()
+}
+
+fn build(config: cli::BuildConfig) {
+    let site = load_site(config.ignore_errors);
+    if !check_rules() && !config.ignore_errors {
+        process::exit(1);
+    }
 
     if config.write {
         if config.force {
             let _ = fs::remove_dir_all(&config.output_folder);
         }
 
-        for entry in &entries {
-            commit_file(
-                &config.output_folder.join(&entry.processed_path),
-                &template::apply(&template, &entries, entry),
-            );
+        for entry in site.entries() {
+            let path = config.output_folder.join(&entry.processed_path);
+            let contents = match site.render(entry) {
+                Ok(contents) => contents,
+                Err(error) => {
+                    println!("failed to process file: {:?}\n  {error}", entry.path);
+                    if config.ignore_errors {
+                        continue;
+                    }
+                    process::exit(1);
+                }
+            };
+            // Static hosts can serve these siblings directly to clients that accept gzip.
+            if config.gzip
+                && matches!(
+                    mime::essence(mime::from_path(&path, &contents)),
+                    "text/html" | "text/css" | "application/xml" | "image/svg+xml"
+                )
+            {
+                let mut gzip_path = path.clone().into_os_string();
+                gzip_path.push(".gz");
+                commit_file(Path::new(&gzip_path), &gzip::encode(&contents));
+            }
+            commit_file(&path, &contents);
         }
     }
 }
@@ -114,6 +147,7 @@ fn deploy() {
         write: true,
         force: true,
         ignore_errors: false,
+        gzip: false,
         output_folder: PathBuf::from(conf::OUTPUT_FOLDER),
     });
 
@@ -132,22 +166,197 @@ fn deploy() {
         .expect("output folder git directory to be delete-able");
 }
 
+fn remove_output(output_folder: &Path, path: &Path) {
+    let output_path = output_folder.join(Entry::processed_path_of(path));
+    let _ = if output_path.is_dir() {
+        fs::remove_dir_all(&output_path)
+    } else {
+        fs::remove_file(&output_path)
+    };
+
+    // Clean up the folders that only existed to hold this output, such as the ones for posts.
+    let mut parent = output_path.parent();
+    while let Some(dir) =
+        parent.filter(|dir| dir.starts_with(output_folder) && *dir != output_folder)
+    {
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+        parent = dir.parent();
+    }
+}
+
+fn move_output(output_folder: &Path, from: &Path, to: &Path) {
+    let from = output_folder.join(Entry::processed_path_of(from));
+    let to = output_folder.join(Entry::processed_path_of(to));
+    if from.exists() {
+        fs::create_dir_all(to.parent().expect("path to have a parent"))
+            .expect("parent directories to be created");
+        fs::rename(from, to).expect("output to be movable");
+    }
+}
+
 fn serve(config: cli::ServeConfig) {
-    if config.watch {
-        thread::spawn(|| {
-            let template = load_template();
+    // Malformed rules are skipped when serving, but they should not go unnoticed.
+    check_rules();
+    let live_reload = config.watch.then(|| Arc::new(LiveReload::new()));
+    let memory = config
+        .memory
+        .then(|| Arc::new(RwLock::new(load_site(true))));
+    // The site in memory would otherwise never change, so it is kept up to date regardless.
+    if config.watch || config.memory {
+        let live_reload = live_reload.clone();
+        let memory = memory.clone();
+        thread::spawn(move || {
             let output_folder = PathBuf::from(conf::OUTPUT_FOLDER);
-            for path in file_watcher::watch(conf::INPUT_FOLDER) {
-                if let Ok(entry) = Entry::load_from_path(path) {
-                    commit_file(
-                        &output_folder.join(&entry.processed_path),
-                        &template::apply(&template, &[], &entry),
-                    );
+            // In memory, outputs are rendered on request, so there is nothing to write.
+            let write_output = memory.is_none();
+            let site = memory.unwrap_or_else(|| Arc::new(RwLock::new(load_site(true))));
+            // Paths that fail to load keep their previous entry, which may still render fine.
+            let mut unloadable = HashSet::<PathBuf>::new();
+
+            for event in file_watcher::watch(conf::INPUT_FOLDER, config.poll) {
+                let mut site = site.write().expect("site lock to not be poisoned");
+                let mut dirty = Vec::new();
+                let mut reload_page = false;
+                let mut failures = Vec::new();
+                let mut recovered = Vec::new();
+                // Files that are gone cannot be broken anymore.
+                if let file_watcher::Event::Removed(path)
+                | file_watcher::Event::Renamed { from: path, .. } = &event
+                {
+                    unloadable.retain(|p| !p.starts_with(path));
+                    recovered.push(path.clone());
+                }
+                let mut load = |site: &mut Site, path: PathBuf| match site.load(path.clone()) {
+                    Ok(dirty) => {
+                        unloadable.remove(&path);
+                        recovered.push(path);
+                        dirty
+                    }
+                    Err(error) => {
+                        unloadable.insert(path.clone());
+                        failures.push((path, error));
+                        Vec::new()
+                    }
+                };
+                match event {
+                    file_watcher::Event::Created(path) | file_watcher::Event::Modified(path) => {
+                        dirty.extend(load(&mut site, path));
+                    }
+                    file_watcher::Event::Removed(path) => {
+                        reload_page = true;
+                        if write_output {
+                            remove_output(&output_folder, &path);
+                        }
+                        dirty.extend(site.unload(&path));
+                    }
+                    file_watcher::Event::Renamed { from, to } => {
+                        reload_page = true;
+                        if to.is_dir() {
+                            if write_output {
+                                move_output(&output_folder, &from, &to);
+                            }
+                            dirty.extend(site.unload(&from));
+                            for dir_entry in walkdir::walk(to) {
+                                dirty.extend(load(&mut site, dir_entry.path()));
+                            }
+                        } else {
+                            if write_output {
+                                remove_output(&output_folder, &from);
+                            }
+                            dirty.extend(site.unload(&from));
+                            dirty.extend(load(&mut site, to));
+                        }
+                    }
+                }
+
+                dirty.sort();
+                dirty.dedup();
+                // Rendering happens even in memory, so that template errors are noticed right away.
+                for &i in &dirty {
+                    let entry = &site.entries()[i];
+                    match site.render(entry) {
+                        Ok(contents) => {
+                            if write_output {
+                                commit_file(&output_folder.join(&entry.processed_path), &contents);
+                            }
+                            if !unloadable.contains(&entry.path) {
+                                recovered.push(entry.path.clone());
+                            }
+                        }
+                        Err(error) => failures.push((entry.path.clone(), error)),
+                    }
+                }
+
+                for (path, error) in &failures {
+                    println!("failed to process file: {path:?}\n  {error}");
+                }
+                let Some(live_reload) = &live_reload else {
+                    continue;
+                };
+
+                // Recovering first, so that a file that loads but fails to render stays failed.
+                for path in recovered {
+                    reload_page |= live_reload.recover(&path);
+                }
+                for (path, error) in failures {
+                    live_reload.fail(&path, &error);
+                    reload_page = true;
+                }
+
+                if reload_page || !dirty.is_empty() {
+                    let style_only = !reload_page
+                        && dirty.iter().all(|&i| {
+                            site.entries()[i]
+                                .processed_path
+                                .extension()
+                                .is_some_and(|e| e == "css")
+                        });
+                    live_reload.notify(if style_only {
+                        live_reload::Change::Style
+                    } else {
+                        live_reload::Change::Page
+                    });
                 }
             }
         });
     }
-    http::server::run();
+    // Brackets are accepted so that IPv6 addresses can be written as they appear in URLs.
+    let host = config
+        .host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(&config.host);
+    let options = http::server::Options {
+        quiet: config.quiet,
+        autoindex: config.autoindex,
+        live_reload,
+        memory,
+        rules_folder: Some(PathBuf::from(conf::INPUT_FOLDER)),
+        handler: None,
+    };
+    let server = match http::Server::bind((host, config.port), conf::OUTPUT_FOLDER, options) {
+        Ok(server) => server,
+        Err(e) => {
+            let host = if host.contains(':') {
+                format!("[{host}]")
+            } else {
+                host.to_owned()
+            };
+            eprintln!(
+                "site: error: could not listen on {host}:{}: {e}",
+                config.port
+            );
+            process::exit(1);
+        }
+    };
+    match server.local_addr() {
+        Ok(address) => println!("serving on http://{address}/"),
+        Err(e) => eprintln!("site: warning: could not tell the bound address: {e}"),
+    }
+    http::shutdown::install_handler();
+    server.run();
 }
 
 fn main() {
@@ -9,7 +9,10 @@ pub struct Tokens<'t> {
     pos: usize,
     possible_text_start: usize,
     next_is_start_of_line: bool,
+    next_is_start_of_item: bool,
     reference_text_start: Option<usize>,
+    in_table: bool,
+    in_table_header: bool,
 }
 
 impl<'t> Iterator for Tokens<'t> {
@@ -26,6 +29,25 @@ impl<'t> Iterator for Tokens<'t> {
             };
         }
 
+        // Like `flush_text`, but dropping the whitespace that surrounds the cell separators.
+        macro_rules! flush_cell_text {
+            () => {
+                let end = self.pos
+                    - self
+                        .text_in(self.possible_text_start, self.pos)
+                        .iter()
+                        .rev()
+                        .take_while(|c| c.is_ascii_whitespace())
+                        .count();
+                if self.possible_text_start < end {
+                    let range = self.possible_text_start..end;
+                    self.possible_text_start = self.pos;
+                    return Some(Token::Text(&self.text[range]));
+                }
+                self.possible_text_start = self.pos;
+            };
+        }
+
         macro_rules! emit {
             ($token:expr => $j:expr) => {
                 self.possible_text_start = $j;
@@ -38,8 +60,19 @@ impl<'t> Iterator for Tokens<'t> {
             let i = self.pos;
             let start_of_line = self.next_is_start_of_line;
             self.next_is_start_of_line = false;
+            let start_of_item = self.next_is_start_of_item;
+            self.next_is_start_of_item = false;
 
             match c {
+                // Table, which must be recognized before the header row is lexed as anything else
+                _ if start_of_line && !self.in_table && self.table_delimiter_at(i).is_some() => {
+                    flush_text!();
+                    let delimiter = self.table_delimiter_at(i).unwrap(); // won't panic due to match guard
+                    self.in_table = true;
+                    self.in_table_header = true;
+                    emit!(Token::Table(delimiter) => self.cell_start(i));
+                }
+
                 // Escape sequences '\X'
                 b'\\' => {
                     flush_text!();
@@ -52,6 +85,20 @@ impl<'t> Iterator for Tokens<'t> {
                     continue;
                 }
 
+                // Autolinks, which would otherwise look like HTML tags
+                b'<' if self.autolink_end(i + 1).is_some() => {
+                    flush_text!();
+                    let j = self.autolink_end(i + 1).unwrap(); // won't panic due to match guard
+                    let uri = &self.text[i + 1..j];
+                    // Email addresses cannot contain a colon, but URIs must have one after the scheme.
+                    let scheme = if uri.contains(&b':') {
+                        b"".as_ref()
+                    } else {
+                        b"mailto:"
+                    };
+                    emit!(Token::Autolink { scheme, uri } => j + 1);
+                }
+
                 // HTML tags that do not contain markdown to be parsed
                 b'<' if self.text_at(i + 1).starts_with(b"pre")
                     || self.text_at(i + 1).starts_with(b"script")
@@ -131,15 +178,27 @@ impl<'t> Iterator for Tokens<'t> {
                 // Unordered-list item
                 b'*' | b'-' if start_of_line && self.char_at(i + 1) == b' ' => {
                     flush_text!();
+                    self.next_is_start_of_item = true;
                     emit!(Token::BeginItem { ordered: false } => i + 2);
                 }
 
                 // Ordered-list item
                 b'0'..=b'9' if start_of_line && self.text_in(i + 1, i + 3) == b". " => {
                     flush_text!();
+                    self.next_is_start_of_item = true;
                     emit!(Token::BeginItem { ordered: true } => i + 3);
                 }
 
+                // Task-list item, which would otherwise look like a reference
+                b'[' if start_of_item
+                    && matches!(self.char_at(i + 1), b' ' | b'x' | b'X')
+                    && self.char_at(i + 2) == b']'
+                    && matches!(self.char_at(i + 3), 0 | b' ' | b'\r' | b'\n') =>
+                {
+                    flush_text!();
+                    emit!(Token::Task { checked: self.char_at(i + 1) != b' ' } => i + 3);
+                }
+
                 // Emphasis
                 b'*' if (i == 0 || self.char_at(i - 1) != b'*')
                     && self
@@ -223,6 +282,27 @@ impl<'t> Iterator for Tokens<'t> {
                     }
                 }
 
+                // Bare links, unless they are already the text of a reference
+                b'h' | b'w'
+                    if self.reference_text_start.is_none()
+                        && (i == 0
+                            || matches!(
+                                self.char_at(i - 1),
+                                b' ' | b'\t' | b'\n' | b'(' | b'*' | b'~'
+                            ))
+                        && self.bare_link_end(i).is_some() =>
+                {
+                    flush_text!();
+                    let j = self.bare_link_end(i).unwrap(); // won't panic due to match guard
+                    let uri = &self.text[i..j];
+                    let scheme = if uri.starts_with(b"www.") {
+                        b"http://".as_ref()
+                    } else {
+                        b""
+                    };
+                    emit!(Token::Autolink { scheme, uri } => j);
+                }
+
                 // Heading
                 b'#' if start_of_line
                     && self
@@ -297,6 +377,34 @@ impl<'t> Iterator for Tokens<'t> {
                     emit!(Token::Quote => i + 1);
                 }
 
+                // Table cell and row separators
+                b'|' if self.in_table => {
+                    flush_cell_text!();
+                    if self.line_at(i + 1).trim_ascii().is_empty() {
+                        // Trailing pipe
+                        self.pos = i + 1 + self.line_at(i + 1).len();
+                        self.possible_text_start = self.pos;
+                        continue;
+                    }
+                    emit!(Token::Cell => self.cell_start(i));
+                }
+
+                b'\n' if self.in_table => {
+                    flush_cell_text!();
+                    if self.in_table_header {
+                        // Skip to the end of the delimiter row, which was already emitted with the table
+                        self.in_table_header = false;
+                        self.pos = self.char_start(b'\n', i + 1);
+                        self.possible_text_start = self.pos;
+                        continue;
+                    }
+                    if table_cell_count(self.line_at(i + 1)).is_some() {
+                        emit!(Token::Row => self.cell_start(i + 1));
+                    }
+                    self.in_table = false;
+                    continue;
+                }
+
                 // Paragraph break
                 b'\n' => {
                     flush_text!();
@@ -414,6 +522,101 @@ impl<'t> Tokens<'t> {
         }
     }
 
+    fn table_delimiter_at(&self, i: usize) -> Option<&'t [u8]> {
+        let header = self.line_at(i);
+        let delimiter = self.line_at(i + header.len() + 1);
+        let columns = table_cell_count(header)?;
+        (is_table_delimiter(delimiter) && table_cell_count(delimiter) == Some(columns))
+            .then_some(delimiter)
+    }
+
+    // Skips the leading pipe and the whitespace around it.
+    fn cell_start(&self, i: usize) -> usize {
+        let mut j = i;
+        while self.char_at(j) == b' ' {
+            j += 1;
+        }
+        if self.char_at(j) == b'|' {
+            j += 1;
+        }
+        while self.char_at(j) == b' ' {
+            j += 1;
+        }
+        j
+    }
+
+    // Returns the position of the closing `>` of either an absolute URI or an email address.
+    fn autolink_end(&self, i: usize) -> Option<usize> {
+        let j = self.char_start_till(b'>', i, i + self.line_at(i).len());
+        let link = self.text_in(i, j);
+        if self.char_at(j) != b'>' || link.is_empty() {
+            return None;
+        }
+
+        let uri = link.iter().position(|&c| c == b':').is_some_and(|k| {
+            (2..=32).contains(&k)
+                && link[0].is_ascii_alphabetic()
+                && link[1..k]
+                    .iter()
+                    .all(|&c| c.is_ascii_alphanumeric() || matches!(c, b'+' | b'.' | b'-'))
+                && link.iter().all(|&c| c > b' ' && c != b'<' && c != 0x7f)
+        });
+        let email = link.iter().position(|&c| c == b'@').is_some_and(|k| {
+            k > 0
+                && link[..k]
+                    .iter()
+                    .all(|&c| c.is_ascii_alphanumeric() || b".!#$%&'*+/=?^_`{|}~-".contains(&c))
+                && link[k + 1..].split(|&c| c == b'.').all(|label| {
+                    !label.is_empty()
+                        && label.len() <= 63
+                        && label[0] != b'-'
+                        && label[label.len() - 1] != b'-'
+                        && label
+                            .iter()
+                            .all(|&c| c.is_ascii_alphanumeric() || c == b'-')
+                })
+        });
+        (uri || email).then_some(j)
+    }
+
+    // Like GitHub, trailing punctuation and unbalanced parenthesis are left out of the link, and
+    // the domain needs a period but no underscores in its last two parts.
+    fn bare_link_end(&self, i: usize) -> Option<usize> {
+        let text = self.text_at(i);
+        let prefix = [b"https://".as_ref(), b"http://", b"www."]
+            .into_iter()
+            .find(|prefix| text.starts_with(prefix))?;
+
+        let mut end = text
+            .iter()
+            .position(|&c| c.is_ascii_whitespace() || c == b'<')
+            .unwrap_or(text.len());
+        while end > prefix.len() {
+            let link = &text[..end];
+            match link[end - 1] {
+                b'?' | b'!' | b'.' | b',' | b':' | b'*' | b'_' | b'~' | b'\'' | b'"' => {}
+                b')' if link.iter().filter(|&&c| c == b')').count()
+                    > link.iter().filter(|&&c| c == b'(').count() => {}
+                _ => break,
+            }
+            end -= 1;
+        }
+
+        let domain = &text[prefix.len()..end];
+        let domain = &domain[..domain
+            .iter()
+            .position(|&c| !(c.is_ascii_alphanumeric() || matches!(c, b'.' | b'-' | b'_')))
+            .unwrap_or(domain.len())];
+        let valid = domain.split(|&c| c == b'.').count() > 1
+            && domain.split(|&c| c == b'.').all(|part| !part.is_empty())
+            && !domain
+                .split(|&c| c == b'.')
+                .rev()
+                .take(2)
+                .any(|part| part.contains(&b'_'));
+        valid.then_some(i + end)
+    }
+
     fn unescaped_reference_end(&self, i: usize) -> Option<usize> {
         self.line_at(i)
             .windows(2)
@@ -428,6 +631,38 @@ pub fn lex(text: &[u8]) -> Tokens<'_> {
         pos: 0,
         possible_text_start: 0,
         next_is_start_of_line: true,
+        next_is_start_of_item: false,
         reference_text_start: None,
+        in_table: false,
+        in_table_header: false,
     }
 }
+
+// Returns `None` if the row has no pipes to separate its cells.
+fn table_cell_count(row: &[u8]) -> Option<usize> {
+    let row = row.trim_ascii();
+    let mut pipes = Vec::new();
+    let mut escaped = false;
+    for (i, &c) in row.iter().enumerate() {
+        if c == b'|' && !escaped {
+            pipes.push(i);
+        }
+        escaped = c == b'\\' && !escaped;
+    }
+
+    let leading = pipes.first() == Some(&0);
+    let trailing = row.len() > 1 && pipes.last() == Some(&(row.len() - 1));
+    (!pipes.is_empty()).then(|| pipes.len() + 1 - leading as usize - trailing as usize)
+}
+
+fn is_table_delimiter(row: &[u8]) -> bool {
+    let row = row.trim_ascii();
+    let row = row.strip_prefix(b"|").unwrap_or(row);
+    let row = row.strip_suffix(b"|").unwrap_or(row);
+    row.split(|&c| c == b'|').all(|cell| {
+        let cell = cell.trim_ascii();
+        let cell = cell.strip_prefix(b":").unwrap_or(cell);
+        let cell = cell.strip_suffix(b":").unwrap_or(cell);
+        !cell.is_empty() && cell.iter().all(|&c| c == b'-')
+    })
+}
@@ -160,8 +160,7 @@ fn test_metadata_ok() {
     let text = b"-";
     assert_eq!(lex(text).collect::<Vec<_>>(), vec![Token::Separator(b'-')]);
 
-    let text = format!("text\n+++\nmeta\n+++\ntext");
-    let text = text.as_bytes();
+    let text = b"text\n+++\nmeta\n+++\ntext";
     assert_eq!(
         lex(text).collect::<Vec<_>>(),
         vec![
@@ -299,6 +298,36 @@ fn test_item() {
     );
 }
 
+#[test]
+fn test_task() {
+    let text = b"- [ ] todo\n1. [x] done\n* [X]\n- [y] no";
+    assert_eq!(
+        lex(text).collect::<Vec<_>>(),
+        vec![
+            Token::BeginItem { ordered: false },
+            Token::Task { checked: false },
+            Token::Text(b" todo"),
+            Token::Break { hard: false },
+            Token::BeginItem { ordered: true },
+            Token::Task { checked: true },
+            Token::Text(b" done"),
+            Token::Break { hard: false },
+            Token::BeginItem { ordered: false },
+            Token::Task { checked: true },
+            Token::Break { hard: false },
+            Token::BeginItem { ordered: false },
+            Token::BeginReference { bang: false },
+            Token::Text(b"y"),
+            Token::EndReference {
+                uri: b"y",
+                alt: b"",
+                lazy: true
+            },
+            Token::Text(b" no"),
+        ]
+    );
+}
+
 #[test]
 fn test_emphasis() {
     let text = b"*1* **2** ***3***";
@@ -503,3 +532,96 @@ fn test_break() {
         ]
     );
 }
+
+#[test]
+fn test_autolinks() {
+    let text = b"<https://a.b/c?d> <me@a.b> <a@b> <em>c</em>";
+    assert_eq!(
+        lex(text).collect::<Vec<_>>(),
+        vec![
+            Token::Autolink {
+                scheme: b"",
+                uri: b"https://a.b/c?d"
+            },
+            Token::Text(b" "),
+            Token::Autolink {
+                scheme: b"mailto:",
+                uri: b"me@a.b"
+            },
+            Token::Text(b" "),
+            Token::Autolink {
+                scheme: b"mailto:",
+                uri: b"a@b"
+            },
+            Token::Text(b" "),
+            Token::Raw(b"<em>c</em>"),
+        ],
+    );
+
+    let text = b"see https://a.b/c_(d)), (www.a.b). http://x, x://a.b, www.a, [https://a.b](u)";
+    assert_eq!(
+        lex(text).collect::<Vec<_>>(),
+        vec![
+            Token::Text(b"see "),
+            Token::Autolink {
+                scheme: b"",
+                uri: b"https://a.b/c_(d)"
+            },
+            Token::Text(b"), ("),
+            Token::Autolink {
+                scheme: b"http://",
+                uri: b"www.a.b"
+            },
+            Token::Text(b"). http://x, x://a.b, www.a, "),
+            Token::BeginReference { bang: false },
+            Token::Text(b"https://a.b"),
+            Token::EndReference {
+                uri: b"u",
+                alt: b"",
+                lazy: false
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_tables() {
+    let text = b"| a | *b* |\n|:--|--:|\n| c \\| d | `e` |\nf | g |\n\ntext";
+    assert_eq!(
+        lex(text).collect::<Vec<_>>(),
+        vec![
+            Token::Table(b"|:--|--:|"),
+            Token::Text(b"a"),
+            Token::Cell,
+            Token::Emphasis(1),
+            Token::Text(b"b"),
+            Token::Emphasis(1),
+            Token::Row,
+            Token::Text(b"c "),
+            Token::Text(b"| d"),
+            Token::Cell,
+            Token::Code(b"e"),
+            Token::Row,
+            Token::Text(b"f"),
+            Token::Cell,
+            Token::Text(b"g"),
+            Token::Break { hard: true },
+            Token::Text(b"text"),
+        ],
+    );
+
+    let text = b"a | b\n--- | ---\n\ncolumns | differ\n--- | --- | ---";
+    assert_eq!(
+        lex(text).collect::<Vec<_>>(),
+        vec![
+            Token::Table(b"--- | ---"),
+            Token::Text(b"a"),
+            Token::Cell,
+            Token::Text(b"b"),
+            Token::Break { hard: true },
+            Token::Text(b"columns | differ"),
+            Token::Break { hard: false },
+            Token::Text(b"--- | --- | ---"),
+        ],
+    );
+}
@@ -10,6 +10,9 @@ pub enum Token<'t> {
     BeginItem {
         ordered: bool,
     },
+    Task {
+        checked: bool,
+    },
     Indent(usize),
     Emphasis(u8),
     Deleted,
@@ -21,6 +24,10 @@ pub enum Token<'t> {
         alt: &'t [u8],
         lazy: bool,
     },
+    Autolink {
+        scheme: &'static [u8],
+        uri: &'t [u8],
+    },
     Heading(u8),
     Fence {
         lang: &'t [u8],
@@ -31,6 +38,9 @@ pub enum Token<'t> {
     Break {
         hard: bool,
     },
+    Table(&'t [u8]),
+    Row,
+    Cell,
 }
 
 impl fmt::Debug for Token<'_> {
@@ -54,6 +64,7 @@ impl fmt::Debug for Token<'_> {
                 .debug_struct("BeginItem")
                 .field("ordered", ordered)
                 .finish(),
+            Self::Task { checked } => f.debug_struct("Task").field("checked", checked).finish(),
             Self::Indent(x) => f.debug_tuple("Indent").field(x).finish(),
             Self::Emphasis(x) => f.debug_tuple("Emphasis").field(x).finish(),
             Self::Deleted => f.write_str("Deleted"),
@@ -67,6 +78,11 @@ impl fmt::Debug for Token<'_> {
                 .field("alt", &String::from_utf8_lossy(alt))
                 .field("lazy", lazy)
                 .finish(),
+            Self::Autolink { scheme, uri } => f
+                .debug_struct("Autolink")
+                .field("scheme", &String::from_utf8_lossy(scheme))
+                .field("uri", &String::from_utf8_lossy(uri))
+                .finish(),
             Self::Heading(x) => f.debug_tuple("Heading").field(x).finish(),
             Self::Fence { lang, text } => f
                 .debug_struct("Fence")
@@ -79,6 +95,12 @@ impl fmt::Debug for Token<'_> {
                 .finish(),
             Self::Quote => f.write_str("Quote"),
             Self::Break { hard } => f.debug_struct("Break").field("hard", hard).finish(),
+            Self::Table(x) => f
+                .debug_tuple("Table")
+                .field(&String::from_utf8_lossy(x))
+                .finish(),
+            Self::Row => f.write_str("Row"),
+            Self::Cell => f.write_str("Cell"),
         }
     }
 }
@@ -2,4 +2,4 @@ mod lexer;
 mod parser;
 
 pub use lexer::{Token, Tokens, Tokens3Window, lex};
-pub use parser::{Node, parse};
+pub use parser::{Alignment, Node, parse};
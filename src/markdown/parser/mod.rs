@@ -2,7 +2,7 @@ mod node;
 #[cfg(test)]
 mod tests;
 
-pub use node::Node;
+pub use node::{Alignment, Node};
 
 use super::{Token, Tokens, Tokens3Window};
 use crate::collections::{Graph, GraphNodeRef as Ref};
@@ -18,6 +18,7 @@ pub fn parse(tokens: Tokens) -> ParseResult {
     let mut cursor = arena.root();
 
     let mut nodes_with_references_to_resolve = Vec::new();
+    let mut table_alignments = Vec::new();
 
     for (prev, token, next) in Tokens3Window::new(tokens) {
         match token {
@@ -103,6 +104,9 @@ pub fn parse(tokens: Tokens) -> ParseResult {
                     .append_child(Node::ListItem)
                     .append_child(Node::Paragraph);
             }
+            Token::Task { checked } => {
+                cursor.append_child(Node::Task { checked });
+            }
             Token::Indent(_) => {}
             Token::Emphasis(strength) => {
                 if !is_in_text_container_at(cursor) {
@@ -143,7 +147,10 @@ pub fn parse(tokens: Tokens) -> ParseResult {
                 cursor = cursor.append_child(if bang {
                     Node::Image(b"")
                 } else {
-                    Node::Reference(b"")
+                    Node::Reference {
+                        scheme: b"",
+                        url: b"",
+                    }
                 });
             }
             Token::EndReference { uri, alt, lazy } => {
@@ -154,14 +161,17 @@ pub fn parse(tokens: Tokens) -> ParseResult {
                     match cursor.value() {
                         Node::Empty => {}
                         Node::Image(_) => cursor.set_value(Node::Image(uri)),
-                        Node::Reference(_) => {
+                        Node::Reference { .. } => {
                             if lazy && uri.starts_with(b"^") {
                                 cursor.set_value(Node::FootnoteReference(&uri[1..]));
                                 while let Some(child) = cursor.child(0) {
                                     child.remove_reparent(false);
                                 }
                             } else {
-                                cursor.set_value(Node::Reference(uri))
+                                cursor.set_value(Node::Reference {
+                                    scheme: b"",
+                                    url: uri,
+                                })
                             }
                         }
                         _ => {
@@ -176,6 +186,19 @@ pub fn parse(tokens: Tokens) -> ParseResult {
                 }
                 cursor = cursor.up();
             }
+            Token::Autolink { scheme, uri } => {
+                // The value of a definition is where other references link to, not a link itself.
+                if matches!(cursor.value(), Node::DefinitionItem(_)) && cursor.child(0).is_none() {
+                    cursor.append_child(Node::Text(uri));
+                    continue;
+                }
+                if !is_in_text_container_at(cursor) {
+                    cursor = cursor.append_child(Node::Paragraph);
+                }
+                cursor
+                    .append_child(Node::Reference { scheme, url: uri })
+                    .append_child(Node::Text(uri));
+            }
             Token::Heading(level) => {
                 // Deliberately not supporting titles in lists for simplicity.
                 cursor = cursor.root().append_child(Node::Heading(level));
@@ -190,9 +213,20 @@ pub fn parse(tokens: Tokens) -> ParseResult {
                 if !is_in_text_container_at(cursor) {
                     cursor = cursor.append_child(Node::Paragraph);
                 }
-                cursor
-                    .append_child(Node::Code)
-                    .append_child(Node::Text(text));
+                let code = cursor.append_child(Node::Code);
+                if is_in_table_at(cursor) {
+                    // Pipes in code must be escaped too, but the backslash is not part of the code.
+                    let mut rest = text;
+                    while let Some(j) = rest.windows(2).position(|w| w == b"\\|") {
+                        if j > 0 {
+                            code.append_child(Node::Text(&rest[..j]));
+                        }
+                        rest = &rest[j + 1..];
+                    }
+                    code.append_child(Node::Text(rest));
+                } else {
+                    code.append_child(Node::Text(text));
+                }
             }
             Token::Quote => {
                 if !is_in_quote_at(cursor) {
@@ -204,7 +238,10 @@ pub fn parse(tokens: Tokens) -> ParseResult {
                     Some(Token::Indent(i)) => i,
                     _ => 0,
                 };
-                if hard {
+                if is_in_table_at(cursor) {
+                    // Tables end with their last row, so whatever follows is outside.
+                    cursor = cursor.root();
+                } else if hard {
                     if is_in_quote_at(cursor) {
                         while is_in_quote_at(cursor) {
                             cursor = cursor.up();
@@ -235,6 +272,44 @@ pub fn parse(tokens: Tokens) -> ParseResult {
                     }
                 }
             }
+            Token::Table(delimiter) => {
+                table_alignments = table_alignments_of(delimiter);
+                cursor = cursor
+                    .root()
+                    .append_child(Node::Table)
+                    .append_child(Node::TableRow)
+                    .append_child(Node::TableCell {
+                        header: true,
+                        alignment: table_alignments[0],
+                    });
+            }
+            Token::Row => {
+                while !matches!(cursor.value(), Node::Table) {
+                    cursor = cursor.up();
+                }
+                cursor = cursor
+                    .append_child(Node::TableRow)
+                    .append_child(Node::TableCell {
+                        header: false,
+                        alignment: table_alignments[0],
+                    });
+            }
+            Token::Cell => {
+                while !matches!(cursor.value(), Node::TableRow) {
+                    cursor = cursor.up();
+                }
+                let header = matches!(
+                    cursor.child(0).map(|cell| cell.value()),
+                    Some(Node::TableCell { header: true, .. })
+                );
+                cursor = cursor.append_child(Node::TableCell {
+                    header,
+                    alignment: table_alignments
+                        .get(cursor.child_count())
+                        .copied()
+                        .unwrap_or_default(),
+                });
+            }
         }
     }
 
@@ -244,6 +319,7 @@ pub fn parse(tokens: Tokens) -> ParseResult {
     trim_joiners(root);
     merge_lists_with_same_indent(root);
     remove_paragraphs_from_simple_lists(root);
+    fit_table_rows_to_header(root);
 
     ParseResult {
         additional_style,
@@ -278,9 +354,14 @@ fn resolve_references<'t>(root: Ref<Node<'t>>, pending: Vec<Ref<Node<'t>>>) {
                     node.append_child(Node::Text(b"]"));
                 }
             }
-            Node::Reference(identifier) => {
+            Node::Reference {
+                url: identifier, ..
+            } => {
                 if let Some(value) = find_definition(root, identifier) {
-                    node.set_value(Node::Reference(value));
+                    node.set_value(Node::Reference {
+                        scheme: b"",
+                        url: value,
+                    });
                 } else {
                     node.set_value(Node::Text(b"["));
                     node.append_child(Node::Text(b"]"));
@@ -389,6 +470,48 @@ fn remove_paragraphs_from_simple_lists(node: Ref<Node>) {
     }
 }
 
+fn table_alignments_of(delimiter: &[u8]) -> Vec<Alignment> {
+    let delimiter = delimiter.trim_ascii();
+    let delimiter = delimiter.strip_prefix(b"|").unwrap_or(delimiter);
+    let delimiter = delimiter.strip_suffix(b"|").unwrap_or(delimiter);
+    delimiter
+        .split(|&c| c == b'|')
+        .map(|cell| {
+            let cell = cell.trim_ascii();
+            match (cell.starts_with(b":"), cell.ends_with(b":")) {
+                (true, true) => Alignment::Center,
+                (true, false) => Alignment::Left,
+                (false, true) => Alignment::Right,
+                (false, false) => Alignment::None,
+            }
+        })
+        .collect()
+}
+
+// Body rows with too many cells are truncated, and those with too few padded, like in GFM.
+fn fit_table_rows_to_header(node: Ref<Node>) {
+    if matches!(node.value(), Node::Table) {
+        let header = node.child(0).expect("table to have a header row");
+        for row in node.children().skip(1) {
+            while row.child_count() > header.child_count() {
+                row.last_child().unwrap().remove_reparent(false);
+            }
+            for cell in header.children().skip(row.child_count()) {
+                if let Node::TableCell { alignment, .. } = cell.value() {
+                    row.append_child(Node::TableCell {
+                        header: false,
+                        alignment,
+                    });
+                }
+            }
+        }
+    } else {
+        for child in node.children() {
+            fit_table_rows_to_header(child);
+        }
+    }
+}
+
 fn is_text_container(node: Ref<Node>) -> bool {
     match node.value() {
         Node::Empty
@@ -400,13 +523,20 @@ fn is_text_container(node: Ref<Node>) -> bool {
         | Node::Separator
         | Node::List { .. }
         | Node::ListItem
+        | Node::Task { .. }
         | Node::Emphasis(_)
         | Node::Deleted
-        | Node::Reference(_)
+        | Node::Reference { .. }
         | Node::Code
         | Node::Quote
-        | Node::FootnoteReference(_) => false,
-        Node::Paragraph | Node::Heading(_) | Node::Pre(_) | Node::DefinitionItem(_) => true,
+        | Node::FootnoteReference(_)
+        | Node::Table
+        | Node::TableRow => false,
+        Node::Paragraph
+        | Node::Heading(_)
+        | Node::Pre(_)
+        | Node::DefinitionItem(_)
+        | Node::TableCell { .. } => true,
     }
 }
 
@@ -414,6 +544,15 @@ fn is_in_text_container_at(node: Ref<Node>) -> bool {
     is_text_container(node) || node.ancestors().any(|node| is_text_container(node))
 }
 
+fn is_in_table_at(node: Ref<Node>) -> bool {
+    matches!(
+        node.value(),
+        Node::Table | Node::TableRow | Node::TableCell { .. }
+    ) || node
+        .ancestors()
+        .any(|node| matches!(node.value(), Node::Table))
+}
+
 fn is_in_quote_at(node: Ref<Node>) -> bool {
     matches!(node.value(), Node::Quote)
         || node
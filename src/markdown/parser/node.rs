@@ -1,5 +1,14 @@
 use std::fmt;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Alignment {
+    #[default]
+    None,
+    Left,
+    Center,
+    Right,
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum Node<'t> {
     Empty,
@@ -7,20 +16,38 @@ pub enum Node<'t> {
     Text(&'t [u8]),
     AltText(&'t [u8]),
     Paragraph,
-    Joiner { inline: bool },
+    Joiner {
+        inline: bool,
+    },
     Separator,
-    List { ordered: bool, indent: usize },
+    List {
+        ordered: bool,
+        indent: usize,
+    },
     ListItem,
+    Task {
+        checked: bool,
+    },
     DefinitionItem(&'t [u8]),
     Emphasis(u8),
     Deleted,
     FootnoteReference(&'t [u8]),
-    Reference(&'t [u8]),
+    // Autolinks may need a scheme that was not written, like `mailto:`, to become a valid URL.
+    Reference {
+        scheme: &'static [u8],
+        url: &'t [u8],
+    },
     Image(&'t [u8]),
     Heading(u8),
     Pre(&'t [u8]),
     Code,
     Quote,
+    Table,
+    TableRow,
+    TableCell {
+        header: bool,
+        alignment: Alignment,
+    },
 }
 
 impl fmt::Debug for Node<'_> {
@@ -35,6 +62,7 @@ impl fmt::Debug for Node<'_> {
             Self::Separator => write!(f, "Separator"),
             Self::List { ordered, indent } => write!(f, "List(ordered={ordered}, indent={indent})"),
             Self::ListItem => write!(f, "ListItem"),
+            Self::Task { checked } => write!(f, "Task(checked={checked})"),
             Self::DefinitionItem(identifier) => {
                 write!(f, "DefinitionItem({})", String::from_utf8_lossy(identifier))
             }
@@ -43,12 +71,22 @@ impl fmt::Debug for Node<'_> {
             Self::FootnoteReference(url) => {
                 write!(f, "FootnoteReference({})", String::from_utf8_lossy(url))
             }
-            Self::Reference(url) => write!(f, "Reference({})", String::from_utf8_lossy(url)),
+            Self::Reference { scheme, url } => write!(
+                f,
+                "Reference({}{})",
+                String::from_utf8_lossy(scheme),
+                String::from_utf8_lossy(url)
+            ),
             Self::Image(url) => write!(f, "Image({})", String::from_utf8_lossy(url)),
             Self::Heading(level) => write!(f, "Heading({level})"),
             Self::Pre(text) => write!(f, "Pre({})", String::from_utf8_lossy(text)),
             Self::Code => write!(f, "Code"),
             Self::Quote => write!(f, "Quote"),
+            Self::Table => write!(f, "Table"),
+            Self::TableRow => write!(f, "TableRow"),
+            Self::TableCell { header, alignment } => {
+                write!(f, "TableCell(header={header}, alignment={alignment:?})")
+            }
         }
     }
 }
@@ -86,7 +86,10 @@ fn test_references() {
     unresolved_reference.append_child(Node::Text(b"]"));
     p.append_child(Node::Text(b" "));
 
-    let reusable = p.append_child(Node::Reference(b"https://example.com/reusable"));
+    let reusable = p.append_child(Node::Reference {
+        scheme: b"",
+        url: b"https://example.com/reusable",
+    });
     reusable.append_child(Node::Text(b"reusable"));
 
     p.append_child(Node::Text(b" footnote"));
@@ -95,7 +98,10 @@ fn test_references() {
 
     p.append_child(Node::Text(b" "));
 
-    let inline = p.append_child(Node::Reference(b"https://example.com/inline"));
+    let inline = p.append_child(Node::Reference {
+        scheme: b"",
+        url: b"https://example.com/inline",
+    });
     inline.append_child(Node::Text(b"inline"));
     inline.append_child(Node::AltText(b"title"));
 
@@ -142,8 +148,11 @@ fn test_paragraph_gets_added() {
     let expected = Graph::new(Node::Empty);
     let expected = expected.root();
     let p = expected.append_child(Node::Paragraph);
-    p.append_child(Node::Reference(b"url"))
-        .append_child(Node::Text(b"ref"));
+    p.append_child(Node::Reference {
+        scheme: b"",
+        url: b"url",
+    })
+    .append_child(Node::Text(b"ref"));
     p.append_child(Node::Text(b" text"));
     assert_eq!(parse(tokens).ast.root(), expected);
 
@@ -300,7 +309,10 @@ fn test_lazy_reference_with_formatting() {
     let expected = expected.root();
     expected
         .append_child(Node::Paragraph)
-        .append_child(Node::Reference(b"url"))
+        .append_child(Node::Reference {
+            scheme: b"",
+            url: b"url",
+        })
         .append_child(Node::Code)
         .append_child(Node::Text(b"lazy"));
     expected
@@ -376,3 +388,107 @@ text
 
     assert_eq!(parse(tokens).ast.root(), expected);
 }
+
+#[test]
+fn test_task_list() {
+    let tokens = lex(b"- [x] done\n- [ ] todo\n- plain");
+
+    let expected = Graph::new(Node::Empty);
+    let ul = expected.root().append_child(Node::List {
+        ordered: false,
+        indent: 0,
+    });
+    let li = ul.append_child(Node::ListItem);
+    li.append_child(Node::Task { checked: true });
+    li.append_child(Node::Text(b" done"));
+    let li = ul.append_child(Node::ListItem);
+    li.append_child(Node::Task { checked: false });
+    li.append_child(Node::Text(b" todo"));
+    ul.append_child(Node::ListItem)
+        .append_child(Node::Text(b"plain"));
+
+    assert_eq!(parse(tokens).ast.root(), expected.root());
+}
+
+#[test]
+fn test_autolinks() {
+    let tokens = lex(b"<a@b.c> www.a.b\n\n[a]: https://a.b");
+
+    let expected = Graph::new(Node::Empty);
+    let p = expected.root().append_child(Node::Paragraph);
+    p.append_child(Node::Reference {
+        scheme: b"mailto:",
+        url: b"a@b.c",
+    })
+    .append_child(Node::Text(b"a@b.c"));
+    p.append_child(Node::Text(b" "));
+    p.append_child(Node::Reference {
+        scheme: b"http://",
+        url: b"www.a.b",
+    })
+    .append_child(Node::Text(b"www.a.b"));
+    expected
+        .root()
+        .append_child(Node::DefinitionItem(b"a"))
+        .append_child(Node::Text(b"https://a.b"));
+
+    assert_eq!(parse(tokens).ast.root(), expected.root());
+}
+
+#[test]
+fn test_tables() {
+    let tokens = lex(br"
+text
+| a | b | c |
+|:--|:-:|--:|
+| `d\|e` |
+| f | g | h | i |
+more text
+"
+    .trim_ascii());
+
+    let expected = Graph::new(Node::Empty);
+    let expected = expected.root();
+    expected
+        .append_child(Node::Paragraph)
+        .append_child(Node::Text(b"text"));
+
+    let alignments = [Alignment::Left, Alignment::Center, Alignment::Right];
+    let table = expected.append_child(Node::Table);
+    let row = table.append_child(Node::TableRow);
+    for (text, alignment) in [&b"a"[..], b"b", b"c"].into_iter().zip(alignments) {
+        row.append_child(Node::TableCell {
+            header: true,
+            alignment,
+        })
+        .append_child(Node::Text(text));
+    }
+    let row = table.append_child(Node::TableRow);
+    let code = row
+        .append_child(Node::TableCell {
+            header: false,
+            alignment: Alignment::Left,
+        })
+        .append_child(Node::Code);
+    code.append_child(Node::Text(b"d"));
+    code.append_child(Node::Text(b"|e"));
+    for alignment in [Alignment::Center, Alignment::Right] {
+        row.append_child(Node::TableCell {
+            header: false,
+            alignment,
+        });
+    }
+    let row = table.append_child(Node::TableRow);
+    for (text, alignment) in [&b"f"[..], b"g", b"h"].into_iter().zip(alignments) {
+        row.append_child(Node::TableCell {
+            header: false,
+            alignment,
+        })
+        .append_child(Node::Text(text));
+    }
+    expected
+        .append_child(Node::Paragraph)
+        .append_child(Node::Text(b"more text"));
+
+    assert_eq!(parse(tokens).ast.root(), expected);
+}
@@ -0,0 +1,168 @@
+use std::path::Path;
+
+pub const OCTET_STREAM: &str = "application/octet-stream";
+pub const TEXT_HTML: &str = "text/html; charset=utf-8";
+pub const TEXT_PLAIN: &str = "text/plain; charset=utf-8";
+
+// Text types carry the charset, since everything the site generates is UTF-8.
+const TYPES: &[(&str, &str)] = &[
+    ("avif", "image/avif"),
+    ("css", "text/css; charset=utf-8"),
+    ("csv", "text/csv; charset=utf-8"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", TEXT_HTML),
+    ("html", TEXT_HTML),
+    ("ico", "image/x-icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript; charset=utf-8"),
+    ("json", "application/json; charset=utf-8"),
+    ("md", "text/markdown; charset=utf-8"),
+    ("mjs", "text/javascript; charset=utf-8"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("ogg", "audio/ogg"),
+    ("otf", "font/otf"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml; charset=utf-8"),
+    ("toml", "application/toml; charset=utf-8"),
+    ("ttf", "font/ttf"),
+    ("txt", TEXT_PLAIN),
+    ("wasm", "application/wasm"),
+    ("webm", "video/webm"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("xml", "application/xml; charset=utf-8"),
+    ("zip", "application/zip"),
+];
+
+// Magic numbers, with `?` standing for any byte.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"RIFF????WEBP", "image/webp"),
+    (b"\0\0\x01\0", "image/x-icon"),
+    (b"%PDF-", "application/pdf"),
+    (b"\0asm", "application/wasm"),
+    (b"OggS", "audio/ogg"),
+    (b"????ftyp", "video/mp4"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"PK\x03\x04", "application/zip"),
+];
+
+pub fn from_extension(extension: &str) -> Option<&'static str> {
+    TYPES
+        .iter()
+        .find(|(ext, _)| ext.eq_ignore_ascii_case(extension))
+        .map(|(_, mime)| *mime)
+}
+
+pub fn sniff(contents: &[u8]) -> &'static str {
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(signature, _)| {
+        contents.len() >= signature.len()
+            && signature
+                .iter()
+                .zip(contents)
+                .all(|(&s, &c)| s == b'?' || s == c)
+    }) {
+        return mime;
+    }
+
+    let text = match str::from_utf8(contents) {
+        Ok(text) => text,
+        Err(_) => return OCTET_STREAM,
+    };
+    if text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+    {
+        return OCTET_STREAM;
+    }
+
+    let start = text.trim_start().as_bytes();
+    let starts_with = |prefix: &str| {
+        start
+            .get(..prefix.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(prefix.as_bytes()))
+    };
+    if starts_with("<!doctype html") || starts_with("<html") {
+        TEXT_HTML
+    } else if starts_with("<?xml") {
+        "application/xml; charset=utf-8"
+    } else {
+        TEXT_PLAIN
+    }
+}
+
+pub fn from_path(path: &Path, contents: &[u8]) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => from_extension(extension).unwrap_or(OCTET_STREAM),
+        None => sniff(contents),
+    }
+}
+
+pub fn essence(mime: &str) -> &str {
+    mime.split_once(';')
+        .map_or(mime, |(essence, _)| essence)
+        .trim()
+}
+
+// Whether the type is worth compressing, which excludes formats that already are.
+pub fn is_compressible(mime: &str) -> bool {
+    let essence = essence(mime);
+    essence.starts_with("text/")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence,
+            "application/json" | "application/toml" | "application/wasm" | "application/xml"
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            from_path(Path::new("blog/atom.xml"), b""),
+            "application/xml; charset=utf-8"
+        );
+        assert_eq!(from_path(Path::new("img/a.WEBP"), b""), "image/webp");
+        assert_eq!(from_path(Path::new("unknown.xyz"), b"text"), OCTET_STREAM);
+        assert_eq!(from_path(Path::new("CNAME"), b"lonami.dev\n"), TEXT_PLAIN);
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n...."), "image/png");
+        assert_eq!(sniff(b"RIFF\x10\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(sniff(b"\0\0\0\x18ftypmp42"), "video/mp4");
+        assert_eq!(sniff(b"\n  <!DOCTYPE html><html>"), TEXT_HTML);
+        assert_eq!(
+            sniff(b"<?xml version=\"1.0\"?>"),
+            "application/xml; charset=utf-8"
+        );
+        assert_eq!(sniff(b""), TEXT_PLAIN);
+        assert_eq!(sniff(b"caf\xc3\xa9\r\n"), TEXT_PLAIN);
+        assert_eq!(sniff(b"\xc3\x28"), OCTET_STREAM);
+        assert_eq!(sniff(b"a\0b"), OCTET_STREAM);
+    }
+
+    #[test]
+    fn test_essence() {
+        assert_eq!(essence(TEXT_HTML), "text/html");
+        assert_eq!(essence("image/png"), "image/png");
+        assert!(is_compressible(TEXT_HTML));
+        assert!(is_compressible("image/svg+xml; charset=utf-8"));
+        assert!(!is_compressible("image/png"));
+        assert!(!is_compressible("application/gzip"));
+    }
+}
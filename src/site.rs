@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+use std::{fs, io, panic};
+
+use crate::entry::{self, Entry};
+use crate::{conf, feed, html, template};
+
+const FEED_PATH: &str = "blog/atom.xml";
+
+pub struct Site {
+    template: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "md")
+}
+
+fn is_template(path: &Path) -> bool {
+    path == PathBuf::from(conf::INPUT_FOLDER).join(conf::TEMPLATE_NAME)
+}
+
+fn is_feed_post(path: &Path) -> bool {
+    is_markdown(path)
+        && path.file_stem().is_none_or(|s| s != "_index")
+        && Entry::path_parent_of(path) == Some(&PathBuf::from(conf::INPUT_FOLDER).join("blog"))
+}
+
+fn depends_on(output: &Entry, input: &Path) -> bool {
+    if output.path == input {
+        true
+    } else if is_template(input) {
+        is_markdown(&output.path)
+    } else if output.processed_path == Path::new(FEED_PATH) {
+        is_feed_post(input)
+    } else {
+        output.append_siblings_listing
+            && is_markdown(input)
+            && Entry::path_parent_of(input) == output.path_parent()
+    }
+}
+
+fn feed_entry(entries: &[Entry]) -> Entry {
+    Entry::from_new_path_with_contents(
+        PathBuf::from(FEED_PATH),
+        feed::from_markdown_entries(entries.iter().filter(|entry| is_feed_post(&entry.path)))
+            .to_string()
+            .into_bytes(),
+    )
+}
+
+pub fn load_template() -> io::Result<Vec<u8>> {
+    let path = PathBuf::from(conf::INPUT_FOLDER).join(conf::TEMPLATE_NAME);
+    Ok(html::minify(&fs::read(path)?))
+}
+
+impl Site {
+    pub fn new(template: Vec<u8>, mut entries: Vec<Entry>) -> Self {
+        entries.push(feed_entry(&entries));
+        Self { template, entries }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn find(&self, processed_path: &Path) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.processed_path == processed_path)
+    }
+
+    // Returns the names directly under the given output directory, and whether they are directories.
+    pub fn list(&self, dir: &Path) -> Vec<(bool, String)> {
+        let mut names = Vec::new();
+        for entry in &self.entries {
+            let mut components = match entry.processed_path.strip_prefix(dir) {
+                Ok(rest) => rest.components(),
+                Err(_) => continue,
+            };
+            if let Some(name) = components.next() {
+                let name = (
+                    components.next().is_some(),
+                    name.as_os_str().to_string_lossy().into_owned(),
+                );
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    pub fn render(&self, entry: &Entry) -> Result<Vec<u8>, entry::Error> {
+        panic::catch_unwind(|| template::apply(&self.template, &self.entries, entry))
+            .map_err(entry::Error::from_panic)
+    }
+
+    // Returns the indices of the entries whose output needs to be regenerated.
+    pub fn load(&mut self, path: PathBuf) -> Result<Vec<usize>, entry::Error> {
+        if is_template(&path) {
+            self.template = load_template()?;
+        } else if path.is_dir() {
+            return Ok(Vec::new());
+        } else {
+            let entry = Entry::load_from_path(path.clone())?;
+            match self.entries.iter().position(|e| e.path == entry.path) {
+                Some(i) => self.entries[i] = entry,
+                None => self.entries.push(entry),
+            }
+            if is_feed_post(&path) {
+                self.update_feed();
+            }
+        }
+
+        Ok(self.dependents(&[path]))
+    }
+
+    // Returns the indices of the remaining entries whose output needs to be regenerated.
+    pub fn unload(&mut self, path: &Path) -> Vec<usize> {
+        let mut removed = Vec::new();
+        self.entries.retain(|entry| {
+            if entry.path.starts_with(path) {
+                removed.push(entry.path.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        if removed.iter().any(|path| is_feed_post(path)) {
+            self.update_feed();
+        }
+
+        self.dependents(&removed)
+    }
+
+    fn update_feed(&mut self) {
+        let i = self
+            .entries
+            .iter()
+            .position(|entry| entry.processed_path == Path::new(FEED_PATH))
+            .expect("feed entry to exist");
+        self.entries[i] = feed_entry(&self.entries);
+    }
+
+    fn dependents(&self, inputs: &[PathBuf]) -> Vec<usize> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| inputs.iter().any(|input| depends_on(entry, input)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> Entry {
+        let mut entry = Entry::from_new_path_with_contents(PathBuf::from(path), Vec::new());
+        entry.append_siblings_listing = entry.path.file_stem().is_some_and(|s| s == "_index");
+        entry
+    }
+
+    #[test]
+    fn test_depends_on() {
+        let template = PathBuf::from(conf::INPUT_FOLDER).join(conf::TEMPLATE_NAME);
+        let post = PathBuf::from("content/blog/post.md");
+        let nested_post = PathBuf::from("content/blog/nested/index.md");
+        let golb_post = PathBuf::from("content/golb/post.md");
+        let style = PathBuf::from("content/style.css");
+
+        let blog_listing = entry("content/blog/_index.md");
+        assert!(depends_on(&blog_listing, &template));
+        assert!(depends_on(&blog_listing, &post));
+        assert!(depends_on(&blog_listing, &nested_post));
+        assert!(!depends_on(&blog_listing, &golb_post));
+        assert!(!depends_on(&blog_listing, &style));
+
+        let feed = entry(FEED_PATH);
+        assert!(!depends_on(&feed, &template));
+        assert!(depends_on(&feed, &post));
+        assert!(!depends_on(&feed, &golb_post));
+
+        let blog_post = entry("content/blog/post.md");
+        assert!(depends_on(&blog_post, &template));
+        assert!(depends_on(&blog_post, &post));
+        assert!(!depends_on(&blog_post, &nested_post));
+
+        let stylesheet = entry("content/style.css");
+        assert!(!depends_on(&stylesheet, &template));
+        assert!(depends_on(&stylesheet, &style));
+    }
+
+    #[test]
+    fn test_find_and_list() {
+        let site = Site::new(
+            Vec::new(),
+            vec![
+                entry("content/index.md"),
+                entry("content/blog/_index.md"),
+                entry("content/blog/post.md"),
+                entry("content/style.css"),
+            ],
+        );
+
+        assert!(site.find(Path::new("blog/post/index.html")).is_some());
+        assert!(site.find(Path::new("blog/post")).is_none());
+        assert!(site.find(Path::new("blog/../index.html")).is_none());
+
+        let mut root = site.list(Path::new(""));
+        root.sort();
+        assert_eq!(
+            root,
+            vec![
+                (false, String::from("index.html")),
+                (false, String::from("style.css")),
+                (true, String::from("blog")),
+            ]
+        );
+        assert_eq!(
+            site.list(Path::new("blog/post")),
+            vec![(false, String::from("index.html"))]
+        );
+        assert!(site.list(Path::new("blog/post/index.html")).is_empty());
+    }
+}
@@ -13,17 +13,18 @@ fn strip<'s>(mut string: &'s [u8], chars: &[u8]) -> &'s [u8] {
     string
 }
 
-pub fn parse(text: &[u8]) -> ParseResult<'_> {
+// On error, returns the line that is not a table header, comment or key-value pair.
+pub fn parse(text: &[u8]) -> Result<ParseResult<'_>, &[u8]> {
     let mut result = ParseResult::new();
 
     for mut line in text.split(|c| matches!(c, b'\r' | b'\n')) {
         line = line.trim_ascii();
-        if line.is_empty() || line.starts_with(b"[") {
+        if line.is_empty() || line.starts_with(b"[") || line.starts_with(b"#") {
             continue;
         }
         let equals_index = match line.iter().position(|&c| c == b'=') {
             Some(i) => i,
-            None => continue,
+            None => return Err(line),
         };
         let name = strip(&line[..equals_index], b"\" ");
         let value = strip(&line[equals_index + 1..], b"\" ");
@@ -40,7 +41,7 @@ pub fn parse(text: &[u8]) -> ParseResult<'_> {
         );
     }
 
-    result
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -52,6 +53,7 @@ mod tests {
         let result = parse(
             br#"title = "Some, title"
 date = 1234-56-78
+# comment
 [taxonomies]
 category = ["cat"]
 tags = ["t", "a", "g"]
@@ -63,6 +65,7 @@ tags = ["t", "a", "g"]
         expected.insert(b"category", vec![b"cat"]);
         expected.insert(b"tags", vec![b"t", b"a", b"g"]);
 
-        assert_eq!(result, expected);
+        assert_eq!(result, Ok(expected));
+        assert_eq!(parse(b"a = 1\n  oops \nb = 2"), Err(b"oops".as_ref()));
     }
 }
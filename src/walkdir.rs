@@ -1,5 +1,5 @@
-use std::fs;
 use std::path::PathBuf;
+use std::{fs, io};
 
 pub struct WalkDir {
     current: Option<fs::ReadDir>,
@@ -23,7 +23,12 @@ impl Iterator for WalkDir {
                 }
                 self.current = None;
             } else if let Some(dir) = self.pending.pop() {
-                self.current = Some(fs::read_dir(dir).expect("dir to be readable"));
+                self.current = match fs::read_dir(dir) {
+                    Ok(current) => Some(current),
+                    // The directory may have been removed since it was listed.
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                    Err(e) => panic!("dir to be readable: {e}"),
+                };
             } else {
                 break None;
             }
@@ -14,6 +14,8 @@ pub const GENERIC_WRITE: i32 = 0x40000000;
 pub const INVALID_HANDLE_VALUE: isize = 0xFFFFFFFFFFFFFFFFusize as isize;
 pub const OPEN_EXISTING: i32 = 3;
 
+pub type PHANDLER_ROUTINE = unsafe extern "system" fn(CtrlType: i32) -> i32;
+
 #[repr(i32)]
 pub enum FILE_ACTION {
     ADDED = 0x00000001,
@@ -55,4 +57,6 @@ unsafe extern "system" {
     ) -> i32;
 
     pub fn CancelIoEx(hFile: isize, lpOverlapped: *const c_void) -> i32;
+
+    pub fn SetConsoleCtrlHandler(HandlerRoutine: PHANDLER_ROUTINE, Add: i32) -> i32;
 }